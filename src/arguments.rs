@@ -6,9 +6,11 @@
 
 //! Argument parsing logic (via `clap`) for vsv.
 
+use std::env;
+use std::ffi::OsString;
 use std::path;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 #[derive(Debug, Parser)]
@@ -30,9 +32,14 @@ pub struct Args {
     #[clap(short, long, value_parser, value_name = "dir")]
     pub dir: Option<path::PathBuf>,
 
+    /// Directories to look for available services in (colon-separated),
+    /// defaults to env SVAVAIL or /etc/sv if unset.
+    #[clap(long, value_name = "dir[:dir...]")]
+    pub avail_dir: Option<String>,
+
     /// Turn on verbose output.
     #[clap(short, long, action = clap::ArgAction::Count)]
-    pub verbose: usize,
+    pub verbose: u8,
 
     /// Turn on tree output.
     #[clap(short, long)]
@@ -46,6 +53,16 @@ pub struct Args {
     #[clap(short, long)]
     pub user: bool,
 
+    /// Manage the services of a chroot or disk image mounted at this
+    /// directory (all paths are looked up inside it).
+    #[clap(long, value_parser, value_name = "dir")]
+    pub root: Option<path::PathBuf>,
+
+    /// Manage the services of the container (or other mount namespace) the
+    /// process with this PID runs in, through /proc/<pid>/root.
+    #[clap(long, value_name = "pid", conflicts_with = "root")]
+    pub target_pid: Option<libc::pid_t>,
+
     #[clap(subcommand)]
     pub command: Option<Commands>,
 }
@@ -62,23 +79,119 @@ pub enum Commands {
         #[clap(short, long)]
         log: bool,
 
+        /// Show the result of the service health checks (`check` scripts).
+        #[clap(short = 'H', long)]
+        health: bool,
+
+        /// Show which xbps package installed each service.
+        #[clap(short, long)]
+        package: bool,
+
+        /// Print the services as JSON.
+        #[clap(long)]
+        json: bool,
+
+        /// Show the services in a runlevel other than the current one.
+        #[clap(long, value_name = "NAME")]
+        runlevel: Option<String>,
+
+        /// Show the system services and every user's services (with a SCOPE
+        /// column).
+        #[clap(long)]
+        all_scopes: bool,
+
         filter: Vec<String>,
     },
 
     /// Enable service(s).
-    Enable { services: Vec<String> },
+    Enable {
+        /// Also start the service(s) now.
+        #[clap(long)]
+        now: bool,
+
+        /// Wait for the service(s) to start (up to $SVWAIT or 7 seconds).
+        #[clap(short, long, requires = "now")]
+        wait: bool,
+
+        services: Vec<String>,
+    },
 
     /// Disable service(s).
-    Disable { services: Vec<String> },
+    Disable {
+        /// Also stop the service(s) now.
+        #[clap(long)]
+        now: bool,
+
+        /// Wait for the service(s) to stop (up to $SVWAIT or 7 seconds).
+        #[clap(short, long, requires = "now")]
+        wait: bool,
+
+        services: Vec<String>,
+    },
 
     /// Add service(s) (symlink from /etc/sv).
-    Add { services: Vec<String> },
+    Add {
+        /// Wait for runsv to take over the service(s) (up to $SVWAIT or 7
+        /// seconds).
+        #[clap(short, long)]
+        wait: bool,
+
+        /// Start the service(s) once runsv has taken over.
+        #[clap(long, requires = "wait", conflicts_with = "down")]
+        start: bool,
+
+        /// Create the down file before adding so the service(s) don't start.
+        #[clap(long)]
+        down: bool,
+
+        /// Add the service(s) to a runlevel other than the current one.
+        #[clap(long, value_name = "NAME")]
+        runlevel: Option<String>,
+
+        services: Vec<String>,
+    },
 
     /// Remove service(s) (remove symlink).
-    Remove { services: Vec<String> },
+    Remove {
+        /// Stop the service(s) and their log service(s) first.
+        #[clap(long)]
+        stop: bool,
+
+        /// Wait for runsv to exit (up to $SVWAIT or 7 seconds).
+        #[clap(short, long)]
+        wait: bool,
+
+        /// Remove the service(s) from a runlevel other than the current one.
+        #[clap(long, value_name = "NAME")]
+        runlevel: Option<String>,
+
+        services: Vec<String>,
+    },
 
     /// List all available services in /etc/sv.
-    Avail,
+    Avail {
+        /// Only show services matching a (fuzzy) search of the name and
+        /// description.
+        #[clap(short, long)]
+        search: Option<String>,
+
+        /// Only show services that are added.
+        #[clap(long, conflicts_with = "not_added")]
+        added: bool,
+
+        /// Only show services that aren't added.
+        #[clap(long)]
+        not_added: bool,
+
+        /// Only show services with files changed since the package installed
+        /// them.
+        #[clap(long)]
+        modified: bool,
+
+        /// Print the services as JSON.
+        #[clap(long)]
+        json: bool,
+    },
 
     /// Start service(s) (up).
     Start { services: Vec<String> },
@@ -135,14 +248,255 @@ pub enum Commands {
     /// Exit the service immediately.
     Exit { services: Vec<String> },
 
+    /// Print every file defining a service (run, log/run, conf, env, ...).
+    Cat {
+        /// Service name.
+        service: String,
+
+        /// Show the values of secret env variables (like `*PASSWORD*`).
+        #[clap(long)]
+        reveal: bool,
+    },
+
+    /// Edit a file defining a service (default `run`) in $EDITOR.
+    Edit {
+        /// Service name.
+        service: String,
+
+        /// File to edit, relative to the service dir.
+        file: Option<String>,
+
+        /// Edit files owned by an xbps package.
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Show files of service(s) changed locally since the package installed
+    /// them.
+    Diff { services: Vec<String> },
+
+    /// Check service directories for common mistakes.
+    Lint {
+        /// Print the results as JSON.
+        #[clap(long)]
+        json: bool,
+
+        /// Services to check (default: every service in the svdir).
+        services: Vec<String>,
+    },
+
+    /// List runlevels (in /etc/runit/runsvdir) and show the current one.
+    Runlevel {
+        #[clap(subcommand)]
+        action: Option<RunlevelAction>,
+    },
+
+    /// Show or change the variables in a service's `conf` file.
+    Conf {
+        /// Service name.
+        service: String,
+
+        /// Restart the service after changing the conf file.
+        #[clap(long, global = true)]
+        restart: bool,
+
+        #[clap(subcommand)]
+        action: Option<ConfAction>,
+    },
+
+    /// Show or change the variables in a service's envdir (`chpst -e`).
+    Env {
+        /// Service name.
+        service: String,
+
+        /// Show the values of secret variables (like `*PASSWORD*`).
+        #[clap(long)]
+        reveal: bool,
+
+        #[clap(subcommand)]
+        action: Option<EnvAction>,
+    },
+
+    /// Copy a service (from the avail dir) under a new name.
+    Clone {
+        /// Service to copy.
+        src: String,
+
+        /// Name of the new service.
+        dst: String,
+
+        /// Add the new service right away.
+        #[clap(long)]
+        add: bool,
+    },
+
+    /// Remove broken service symlinks (services that no longer exist).
+    Prune {
+        /// Don't ask for confirmation.
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Only list the broken services.
+        #[clap(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Create a new service (in the first avail dir).
+    New {
+        /// Service name.
+        name: String,
+
+        /// Run the command as this user (via `chpst -u`).
+        #[clap(long, value_name = "user")]
+        user: Option<String>,
+
+        /// Command to run in the `finish` script.
+        #[clap(long, value_name = "cmd")]
+        finish: Option<String>,
+
+        /// Command to run in the `check` script.
+        #[clap(long, value_name = "cmd")]
+        check: Option<String>,
+
+        /// Create a log service using this logger.
+        #[clap(long, value_enum)]
+        log: Option<Logger>,
+
+        /// Add the service without asking.
+        #[clap(long)]
+        add: bool,
+
+        /// Command (and arguments) to run.
+        #[clap(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Show the audit log of administrative actions.
+    Audit {
+        /// Only show actions on this service.
+        service: Option<String>,
+
+        /// Only show actions by this user (name, uid, or SUDO_USER).
+        #[clap(long, value_name = "user")]
+        user: Option<String>,
+    },
+
+    /// Undo the last (or given) transaction.
+    Undo {
+        /// Transaction to undo, defaults to the most recent one.
+        transaction: Option<String>,
+
+        /// List recorded transactions instead.
+        #[clap(short, long, conflicts_with = "transaction")]
+        list: bool,
+    },
+
+    /// Run in sv(8) compatible mode (same commands, options, and output).
+    #[clap(disable_help_flag = true)]
+    Sv {
+        #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// List or run the custom actions (`actions/*`) of a service.
+    Action {
+        /// Service name.
+        service: String,
+
+        /// Action to run, lists the available actions if not given.
+        action: Option<String>,
+
+        /// Arguments passed to the action.
+        #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Run service health checks (`check` scripts).
+    Check {
+        /// Seconds to wait for a check before it is considered unhealthy.
+        #[clap(long, value_name = "secs")]
+        timeout: Option<u64>,
+
+        /// Services to check, defaults to all services.
+        services: Vec<String>,
+    },
+
     /// Generate shell completions.
     Completions {
         /// The shell to generate the completions for.
         #[clap(value_enum)]
         shell: Shell,
     },
+
+    #[clap(external_subcommand)]
+    External(Vec<String>),
+}
+
+/// `vsv conf` actions.
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum ConfAction {
+    /// Set variable(s), replacing any existing assignment.
+    Set {
+        #[clap(value_name = "KEY=VALUE")]
+        assignments: Vec<String>,
+    },
+
+    /// Remove variable(s).
+    Unset {
+        #[clap(value_name = "KEY")]
+        keys: Vec<String>,
+    },
+
+    /// Open the conf file in $EDITOR.
+    Edit,
+}
+
+/// `vsv env` actions.
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum EnvAction {
+    /// Set variable(s), one file per variable.
+    Set {
+        #[clap(value_name = "KEY=VALUE")]
+        assignments: Vec<String>,
+    },
+
+    /// Remove variable(s).
+    Unset {
+        #[clap(value_name = "KEY")]
+        keys: Vec<String>,
+    },
+}
+
+/// `vsv runlevel` actions.
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum RunlevelAction {
+    /// Switch to another runlevel (like runsvchdir(8)).
+    Switch { runlevel: String },
+
+    /// Show which services are added to which runlevels.
+    Matrix,
+}
+
+/// Loggers supported for new log services.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Logger {
+    /// Log to syslog via vlogger(8).
+    Vlogger,
+    /// Log to /var/log/<name> via svlogd(8).
+    Svlogd,
 }
 
 pub fn parse() -> Args {
-    Args::parse()
+    let mut args: Vec<OsString> = env::args_os().collect();
+
+    // called as `sv` (e.g. a symlink) is the same as `vsv sv`
+    let is_sv = args
+        .first()
+        .and_then(|arg0| path::Path::new(arg0).file_name())
+        .is_some_and(|name| name == "sv");
+    if is_sv {
+        args.insert(1, OsString::from("sv"));
+    }
+
+    Args::parse_from(args)
 }
@@ -0,0 +1,250 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! Audit log of mutating (administrative) actions.
+//!
+//! Every control pipe write, `down` file change, and symlink add/remove is
+//! appended as a single tab-separated line to the audit log (and optionally
+//! sent to syslog via `/dev/log`).
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::process;
+use std::time;
+
+use anyhow::{anyhow, Context, Result};
+use yansi::Paint;
+
+use crate::config::Config;
+use crate::utils;
+use crate::utils::verbose;
+
+/// Socket used for syslog messages.
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Number of tab-separated fields in an audit log line.
+const NUM_FIELDS: usize = 7;
+
+/// A single audit log record.
+#[derive(Debug)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub uid: libc::uid_t,
+    pub sudo_user: Option<String>,
+    pub command: String,
+    pub service: String,
+    pub previous: String,
+    pub outcome: String,
+}
+
+impl AuditRecord {
+    /// Create a new record for the current time and user.
+    pub fn new(
+        command: &str,
+        service: &str,
+        previous: &str,
+        outcome: &str,
+    ) -> Self {
+        let timestamp = utils::format_timestamp(time::SystemTime::now());
+        let uid = unsafe { libc::getuid() };
+        let sudo_user = env::var("SUDO_USER").ok().filter(|s| !s.is_empty());
+
+        Self {
+            timestamp,
+            uid,
+            sudo_user,
+            command: sanitize(command),
+            service: sanitize(service),
+            previous: sanitize(previous),
+            outcome: sanitize(outcome),
+        }
+    }
+
+    /// Parse a record from a single line of the audit log.
+    pub fn parse(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != NUM_FIELDS {
+            return Err(anyhow!(
+                "expected {} fields, found {}",
+                NUM_FIELDS,
+                fields.len()
+            ));
+        }
+
+        let uid = fields[1]
+            .parse()
+            .with_context(|| format!("invalid uid: {:?}", fields[1]))?;
+        let sudo_user = match fields[2] {
+            "-" => None,
+            s => Some(s.to_string()),
+        };
+
+        Ok(Self {
+            timestamp: fields[0].to_string(),
+            uid,
+            sudo_user,
+            command: fields[3].to_string(),
+            service: fields[4].to_string(),
+            previous: fields[5].to_string(),
+            outcome: fields[6].to_string(),
+        })
+    }
+
+    /// Name of the user for the real uid of this record.
+    pub fn username(&self) -> String {
+        utils::get_username(self.uid).unwrap_or_else(|| self.uid.to_string())
+    }
+
+    /// Check if this record was made by the given user (name or uid).
+    pub fn matches_user(&self, user: &str) -> bool {
+        self.sudo_user.as_deref() == Some(user)
+            || self.uid.to_string() == user
+            || self.username() == user
+    }
+
+    /// Whether the action this record describes succeeded.
+    pub fn succeeded(&self) -> bool {
+        self.outcome == "ok"
+    }
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = [
+            self.timestamp.as_str(),
+            &self.uid.to_string(),
+            self.sudo_user.as_deref().unwrap_or("-"),
+            &self.command,
+            &self.service,
+            &self.previous,
+            &self.outcome,
+        ];
+
+        fields.join("\t").fmt(f)
+    }
+}
+
+/**
+ * Record a mutating action to the audit log (and syslog if enabled).
+ *
+ * Failing to write the audit record never fails the action itself, a warning
+ * is printed to stderr instead.
+ */
+pub fn record<T, E: fmt::Display>(
+    cfg: &Config,
+    command: &str,
+    service: &str,
+    previous: &str,
+    result: &std::result::Result<T, E>,
+) {
+    let outcome = match result {
+        Ok(_) => "ok".to_string(),
+        Err(err) => format!("failed: {}", err),
+    };
+
+    let rec = AuditRecord::new(command, service, previous, &outcome);
+
+    if let Err(err) = append(&cfg.audit_log, &rec) {
+        eprintln!(
+            "{}: failed to write audit log {:?}: {:#}",
+            "warning".yellow(),
+            cfg.audit_log,
+            err
+        );
+    }
+
+    if cfg.audit_syslog {
+        if let Err(err) = send_syslog(&rec) {
+            eprintln!(
+                "{}: failed to send audit record to syslog: {:#}",
+                "warning".yellow(),
+                err
+            );
+        }
+    }
+}
+
+/**
+ * Read all records from the audit log.
+ *
+ * Malformed or truncated lines (e.g. from a crash mid-write) are skipped with
+ * a warning in verbose mode instead of making the whole log unreadable.
+ */
+pub fn read_records(cfg: &Config) -> Result<Vec<AuditRecord>> {
+    let path = &cfg.audit_log;
+    let content = fs::read(path)
+        .with_context(|| format!("failed to read audit log {:?}", path))?;
+    let content = String::from_utf8_lossy(&content);
+
+    let mut records = vec![];
+    for (i, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        match AuditRecord::parse(line) {
+            Ok(rec) => records.push(rec),
+            Err(err) => verbose!(
+                cfg,
+                "{:?}: line {}: skipping invalid record: {:#}",
+                path,
+                i + 1,
+                err
+            ),
+        }
+    }
+
+    Ok(records)
+}
+
+/// Append a single record to the audit log, creating it if needed.
+fn append(path: &Path, rec: &AuditRecord) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {:?}", dir))?;
+    }
+
+    let mut f =
+        OpenOptions::new().create(true).append(true).mode(0o640).open(path)?;
+
+    writeln!(f, "{}", rec)?;
+
+    Ok(())
+}
+
+/// Send a single record to syslog (authpriv.info).
+fn send_syslog(rec: &AuditRecord) -> Result<()> {
+    let priority = libc::LOG_AUTHPRIV | libc::LOG_INFO;
+    let msg = format!(
+        "<{}>vsv[{}]: uid={} sudo_user={} command={} service={} \
+         previous={} outcome={}",
+        priority,
+        process::id(),
+        rec.uid,
+        rec.sudo_user.as_deref().unwrap_or("-"),
+        rec.command,
+        rec.service,
+        rec.previous,
+        rec.outcome
+    );
+
+    let sock = UnixDatagram::unbound()?;
+    sock.send_to(msg.as_bytes(), SYSLOG_SOCKET)
+        .with_context(|| format!("failed to send to {}", SYSLOG_SOCKET))?;
+
+    Ok(())
+}
+
+/// Make a string safe to store as a single field in the audit log.
+fn sanitize(s: &str) -> String {
+    s.replace(['\t', '\n', '\r'], " ")
+}
@@ -0,0 +1,71 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv action` (custom per-service actions from `<svc>/actions/`).
+
+use std::io;
+use std::io::Write;
+
+use anyhow::{ensure, Result};
+use yansi::Paint;
+
+use crate::audit;
+use crate::config::Config;
+use crate::runit::RunitService;
+
+/// Handle `vsv action <svc> [name [args...]]`.
+pub fn do_action(
+    cfg: &Config,
+    name: &str,
+    action: Option<&str>,
+    args: &[String],
+) -> Result<()> {
+    let svc = RunitService::new(name, &cfg.service_path(name)?);
+    ensure!(svc.path.is_dir(), "service {} not found", name);
+
+    let Some(action) = action else {
+        return list_actions(&svc);
+    };
+
+    print!("{} {} for service {}... ", "running".bold(), action, name.bold());
+    io::stdout().flush()?;
+
+    let ret = svc.run_action(action, args);
+    audit::record(cfg, &format!("action {}", action), name, "-", &ret);
+
+    match ret {
+        Ok(()) => {
+            println!("{}", "ok".green());
+            Ok(())
+        }
+        Err(err) => {
+            println!("{}", "failed".red());
+            Err(err)
+        }
+    }
+}
+
+/// Handle `vsv action <svc>` (list the actions for a service).
+fn list_actions(svc: &RunitService) -> Result<()> {
+    let actions = svc.actions()?;
+
+    if actions.is_empty() {
+        println!("{}", format!("no actions for service {}", svc.name).dim());
+        return Ok(());
+    }
+
+    println!("{}", format!("Actions for service {}:", svc.name).bold());
+
+    for (action, executable) in actions {
+        if executable {
+            println!("{}", action);
+        } else {
+            println!("{} {}", action, "(not executable)".red());
+        }
+    }
+
+    Ok(())
+}
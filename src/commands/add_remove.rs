@@ -6,37 +6,54 @@
 
 //! `vsv add`, `vsv remove`, and `vsv avail`.
 
+use libc::pid_t;
 use std::fs;
+use std::io;
+use std::io::Write;
 use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
 
-use anyhow::{ensure, Context, Result};
-use yansi::Paint;
+use anyhow::{anyhow, ensure, Context, Result};
+use yansi::{Paint, Style};
 
+use crate::audit;
+use crate::commands::diff;
 use crate::config::Config;
-use crate::runit::get_services;
+use crate::journal::Recorder;
+use crate::runit::{
+    self, get_services, split_instance, RunitCommand, RunitService,
+};
+use crate::utils;
+use crate::utils::verbose;
+use crate::xbps::{Package, PkgDb};
 
 /// Handle `vsv add`.
-pub fn do_add(cfg: &Config) -> Result<()> {
-    ensure!(!cfg.operands.is_empty(), "at least one (1) service required");
+pub fn do_add(
+    cfg: &Config,
+    services: &[String],
+    start: bool,
+    down: bool,
+) -> Result<()> {
+    ensure!(!services.is_empty(), "at least one (1) service required");
 
     let mut had_error = false;
+    let recorder = Recorder::begin(cfg, "add");
 
-    for name in &cfg.operands {
-        let source = cfg.avail_dir.join(name);
+    for name in services {
         let target = cfg.svdir.join(name);
 
         print!("{} service {}... ", "adding".bold(), name.bold());
 
-        if !source.exists() {
+        if name.ends_with('@') {
             println!(
                 "{}",
-                format!("failed! {} does not exist", source.display()).red()
+                "failed! service is a template (add name@instance)".red()
             );
             had_error = true;
             continue;
         }
 
-        if target.exists() {
+        if cfg.exists(&target) {
             println!(
                 "{}",
                 "failed! service already added (target exists)".red()
@@ -45,13 +62,60 @@ pub fn do_add(cfg: &Config) -> Result<()> {
             continue;
         }
 
-        // Create the symlink: /etc/sv/<name> -> /var/service/<name>
-        match symlink(&source, &target) {
-            Ok(()) => println!("{}", "done".green()),
+        let (source, instantiated) = match find_or_instantiate(cfg, name) {
+            Ok(found) => found,
             Err(err) => {
                 println!("{}", format!("failed! {}", err).red());
                 had_error = true;
+                continue;
+            }
+        };
+
+        // create the down file first so runsv doesn't start the service
+        if down {
+            let src_svc = RunitService::new(name, &source);
+            recorder.snapshot_enabled(&src_svc);
+            let ret = src_svc.disable();
+            audit::record(cfg, "disable", name, "enabled", &ret);
+
+            if let Err(err) = ret {
+                println!("{}", format!("failed! {}", err).red());
+                had_error = true;
+                if instantiated {
+                    remove_instance(&source);
+                }
+                continue;
+            }
+        }
+
+        // Create the symlink: /etc/sv/<name> -> /var/service/<name>
+        let svc = RunitService::new(name, &target);
+        recorder.snapshot_link(&svc);
+        let ret = link_target(cfg, &source)
+            .and_then(|source| Ok(symlink(source, &target)?));
+        audit::record(cfg, "add", name, "not added", &ret);
+
+        if let Err(err) = ret {
+            println!("{}", format!("failed! {}", err).red());
+            had_error = true;
+
+            if instantiated {
+                remove_instance(&source);
             }
+            continue;
+        }
+
+        if !cfg.wait {
+            println!("{}", "done".green());
+            continue;
+        }
+
+        print!("{}, waiting for runsv... ", "done".green());
+        io::stdout().flush()?;
+
+        if let Err(err) = wait_supervised(cfg, &svc, start) {
+            println!("{}", format!("failed! {}", err).red());
+            had_error = true;
         }
     }
 
@@ -60,18 +124,106 @@ pub fn do_add(cfg: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Remove an instance that failed to be added, so nothing is left behind
+/// that no service points to.
+fn remove_instance(dir: &Path) {
+    if let Err(err) = fs::remove_dir_all(dir) {
+        eprintln!(
+            "{}: failed to remove {:?}: {}",
+            "warning".yellow(),
+            dir,
+            err
+        );
+    }
+}
+
+/// Find a service in the avail dirs, instantiating it from its template
+/// (`name@`) if it is a template instance that doesn't exist yet.  Returns
+/// the service directory and whether it was just instantiated.
+fn find_or_instantiate(cfg: &Config, name: &str) -> Result<(PathBuf, bool)> {
+    if let Some(source) = cfg.find_avail(name) {
+        return Ok((source, false));
+    }
+
+    let not_found =
+        || anyhow!("not found in avail dirs ({})", cfg.avail_path_string());
+
+    let (base, instance) = split_instance(name).ok_or_else(not_found)?;
+    let template =
+        cfg.find_avail(&format!("{}@", base)).ok_or_else(not_found)?;
+
+    let ret = runit::instantiate(&template, instance, cfg.root.is_some());
+    audit::record(cfg, "instantiate", name, "-", &ret);
+    let source = ret?;
+    print!("{}, ", "instantiated".green());
+
+    Ok((source, true))
+}
+
+/**
+ * Get what the service symlink should point to: the service dir itself, or
+ * (with `--root`) a relative path to it so the link works both from the host
+ * and from inside the target root.
+ */
+fn link_target(cfg: &Config, source: &Path) -> Result<PathBuf> {
+    if cfg.root.is_none() {
+        return Ok(source.to_path_buf());
+    }
+
+    let svdir = cfg
+        .canonicalize(&cfg.svdir)
+        .with_context(|| format!("failed to resolve {:?}", cfg.svdir))?;
+    let source = cfg
+        .canonicalize(source)
+        .with_context(|| format!("failed to resolve {:?}", source))?;
+
+    Ok(utils::relative_path(&svdir, &source))
+}
+
+/// Wait for runsv to take over a newly added service and optionally start it.
+fn wait_supervised(
+    cfg: &Config,
+    svc: &RunitService,
+    start: bool,
+) -> Result<()> {
+    let control = svc.path.join("supervise").join("control");
+    let supervised =
+        utils::wait_until(cfg.wait_timeout, || svc.valid() && control.exists());
+    ensure!(
+        supervised,
+        "timed out after {}s (is runsvdir running?)",
+        cfg.wait_timeout.as_secs()
+    );
+
+    if !start {
+        println!("{}", "supervised".green());
+        return Ok(());
+    }
+
+    let previous = svc.state_string();
+    let ret = svc.control(RunitCommand::Up);
+    audit::record(cfg, "start", &svc.name, &previous, &ret);
+    ret?;
+
+    println!("{}", "supervised, started".green());
+
+    Ok(())
+}
+
 /// Handle `vsv remove`.
-pub fn do_remove(cfg: &Config) -> Result<()> {
+pub fn do_remove(cfg: &Config, stop: bool) -> Result<()> {
     ensure!(!cfg.operands.is_empty(), "at least one (1) service required");
 
     let mut had_error = false;
+    let recorder = Recorder::begin(cfg, "remove");
 
     for name in &cfg.operands {
         let target = cfg.svdir.join(name);
 
         print!("{} service {}... ", "removing".bold(), name.bold());
 
-        if !target.exists() {
+        // broken symlinks (the service dir is gone) can still be removed
+        if !cfg.exists(&target) && !cfg.is_broken_link(&target) {
             println!("{}", "failed! service not found".red());
             had_error = true;
             continue;
@@ -104,12 +256,80 @@ pub fn do_remove(cfg: &Config) -> Result<()> {
             }
         }
 
-        match fs::remove_file(&target) {
-            Ok(()) => println!("{}", "done".green()),
-            Err(err) => {
+        let svc = RunitService::new(name, &target);
+
+        // everything that should be gone once runsv exits (gathered before
+        // the symlink is removed, runsv runs inside the service directory)
+        let service_dir =
+            cfg.canonicalize(&target).unwrap_or_else(|_| target.clone());
+        let runsv_pids =
+            utils::find_runsv_pids(name, &service_dir, &cfg.proc_path);
+        let mut pids: Vec<pid_t> = [svc.get_status(), svc.get_log_status()]
+            .into_iter()
+            .filter_map(|status| status.ok().and_then(|status| status.pid))
+            .collect();
+        pids.extend(&runsv_pids);
+
+        if stop {
+            if let Err(err) = stop_service(cfg, &recorder, &svc) {
                 println!("{}", format!("failed! {}", err).red());
                 had_error = true;
+                continue;
             }
+            print!("{}, ", "stopped".green());
+        }
+
+        recorder.snapshot_link(&svc);
+        let ret = fs::remove_file(&target);
+        audit::record(cfg, "remove", name, "added", &ret);
+
+        if let Err(err) = ret {
+            println!("{}", format!("failed! {}", err).red());
+            had_error = true;
+            continue;
+        }
+
+        if !cfg.wait {
+            println!("{}", "done".green());
+            continue;
+        }
+
+        print!("{}, waiting for runsv... ", "done".green());
+        io::stdout().flush()?;
+
+        let gone = utils::wait_until(cfg.wait_timeout, || {
+            runsv_pids
+                .iter()
+                .all(|pid| !utils::pid_exists(*pid, &cfg.proc_path))
+        });
+        if !gone {
+            println!(
+                "{}",
+                format!(
+                    "failed! runsv still running after {}s",
+                    cfg.wait_timeout.as_secs()
+                )
+                .red()
+            );
+            had_error = true;
+            continue;
+        }
+        println!("{}", "exited".green());
+
+        // the service (or its logger) may have ignored TERM
+        for pid in pids {
+            if !utils::pid_exists(pid, &cfg.proc_path) {
+                continue;
+            }
+
+            let cmd = utils::get_command_from_pid(pid, &cfg.proc_path)
+                .unwrap_or_else(|_| "?".to_string());
+            println!(
+                "{}: process {} ({}) is still running",
+                "warning".yellow(),
+                pid,
+                cmd
+            );
         }
     }
 
@@ -118,31 +338,262 @@ pub fn do_remove(cfg: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Stop a service and its log service (if any) before removing it.
+fn stop_service(
+    cfg: &Config,
+    recorder: &Recorder,
+    svc: &RunitService,
+) -> Result<()> {
+    let log =
+        RunitService::new(&format!("{}/log", svc.name), &svc.path.join("log"));
+
+    for svc in [svc, &log] {
+        if !svc.valid() {
+            continue;
+        }
+
+        recorder.snapshot_want(svc);
+        let previous = svc.state_string();
+        let ret = svc.control(RunitCommand::Down);
+        audit::record(cfg, "stop", &svc.name, &previous, &ret);
+        ret?;
+    }
+
+    Ok(())
+}
+
+/// Options for `vsv avail`.
+#[derive(Debug, Default)]
+pub struct AvailOptions<'a> {
+    /// Only show services matching this (fuzzy) search.
+    pub search: Option<&'a str>,
+    pub added: bool,
+    pub not_added: bool,
+    /// Only show services changed since their package installed them.
+    pub modified: bool,
+    pub json: bool,
+}
+
 /// Handle `vsv avail`.
-pub fn do_avail(cfg: &Config) -> Result<()> {
-    // Get list of services in /etc/sv (avail_dir)
-    // We pass `None::<&str>` to explicitly tell the compiler the type of the filter is &str
-    let services = get_services(&cfg.avail_dir, false, None::<&str>)
-        .context(format!("failed to list services in {:?}", cfg.avail_dir))?;
+pub fn do_avail(cfg: &Config, opts: &AvailOptions) -> Result<()> {
+    // Get list of services in every avail dir, the first dir in the search
+    // path with a given service is the one `vsv add` would use
+    let mut services: Vec<RunitService> = vec![];
+    for dir in &cfg.avail_dirs {
+        if !dir.is_dir() {
+            verbose!(cfg, "skipping avail dir {:?} (not a directory)", dir);
+            continue;
+        }
+
+        // We pass `None::<&str>` to explicitly tell the compiler the type of the filter is &str
+        let found = get_services(dir, false, None::<&str>, cfg.root.as_deref())
+            .with_context(|| format!("failed to list services in {:?}", dir))?;
+        for svc in found {
+            if cfg.is_broken_link(&svc.path) {
+                continue;
+            }
+            if !services.iter().any(|s| s.name == svc.name) {
+                services.push(svc);
+            }
+        }
+    }
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let pkgdb = PkgDb::load(&cfg.xbps_dbdir, cfg.root.as_deref());
+
+    let mut rows = vec![];
+    for svc in services {
+        let target = cfg.svdir.join(&svc.name);
+        let added = cfg.exists(&target) || cfg.is_broken_link(&target);
+        let (mut status, mut style) = if svc.name.ends_with('@') {
+            ("template", Style::default().cyan())
+        } else if added {
+            ("added", Style::default().green())
+        } else {
+            ("avail", Style::default().dim())
+        };
+
+        if (opts.added && !added) || (opts.not_added && added) {
+            continue;
+        }
+
+        let package = cfg
+            .canonicalize(&svc.path)
+            .ok()
+            .and_then(|dir| pkgdb.service_owner(&dir).cloned());
+        if opts.modified {
+            let changed = match (&package, cfg.canonicalize(&svc.path)) {
+                (Some(pkg), Ok(dir)) => {
+                    match diff::service_changes(&pkgdb, &pkg.name, &dir) {
+                        Ok(changes) => !changes.is_empty(),
+                        Err(err) => {
+                            // show it, we can't tell if it was modified
+                            eprintln!(
+                                "{}: failed to check {} for changes: {:#}",
+                                "warning".yellow(),
+                                svc.name,
+                                err
+                            );
+                            (status, style) =
+                                ("unknown", Style::default().yellow());
+                            true
+                        }
+                    }
+                }
+                _ => false,
+            };
+            if !changed {
+                continue;
+            }
+        }
+
+        let description = svc
+            .description()
+            .or_else(|| package.as_ref().map(|pkg| pkg.short_desc.clone()))
+            .unwrap_or_default();
+
+        if let Some(search) = opts.search {
+            if !fuzzy_match(search, &svc.name, &description) {
+                continue;
+            }
+        }
+
+        let source = svc.path.parent().unwrap_or(&svc.path).to_path_buf();
+        rows.push(AvailRow {
+            svc,
+            status,
+            style,
+            package,
+            source,
+            description,
+        });
+    }
+
+    if opts.json {
+        let rows: Vec<String> = rows.iter().map(AvailRow::to_json).collect();
+        println!("[{}]", rows.join(","));
+        return Ok(());
+    }
 
     println!(
         "{}",
-        format!("Available services in {:?}:", cfg.avail_dir).bold()
+        format!("Available services in {}:", cfg.avail_path_string()).bold()
     );
 
     // Calculate max length for alignment, ensuring a minimum of 20
     let name_width =
-        services.iter().map(|s| s.name.len()).max().unwrap_or(0).max(20);
+        rows.iter().map(|r| r.svc.name.len()).max().unwrap_or(0).max(20);
+    let package_width =
+        rows.iter().map(|r| r.pkgver().len()).max().unwrap_or(0).max(7);
+    let source_width = rows
+        .iter()
+        .map(|r| r.source.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
 
-    println!("{: <width$} {: <10}", "SERVICE", "STATUS", width = name_width);
+    println!(
+        "{: <name_width$} {: <10} {: <package_width$} {: <source_width$} \
+         DESCRIPTION",
+        "SERVICE",
+        "STATUS",
+        "PACKAGE",
+        "SOURCE",
+        name_width = name_width,
+        package_width = package_width,
+        source_width = source_width
+    );
 
-    for svc in services {
-        let target = cfg.svdir.join(&svc.name);
-        let status =
-            if target.exists() { "added".green() } else { "avail".dim() };
+    for row in rows {
+        let status = format!("{: <10}", row.status);
+        let status = status.paint(row.style);
+        let package =
+            format!("{: <width$}", row.pkgver(), width = package_width);
+        let package = match row.package {
+            Some(_) => package.blue(),
+            None => package.dim(),
+        };
+        let source = row.source.display().to_string();
 
-        println!("{: <width$} {}", svc.name, status, width = name_width);
+        if row.description.is_empty() {
+            println!(
+                "{: <width$} {} {} {}",
+                row.svc.name,
+                status,
+                package,
+                source.dim(),
+                width = name_width
+            );
+            continue;
+        }
+
+        let source = format!("{: <width$}", source, width = source_width);
+        println!(
+            "{: <width$} {} {} {} {}",
+            row.svc.name,
+            status,
+            package,
+            source.dim(),
+            row.description,
+            width = name_width
+        );
     }
 
     Ok(())
 }
+
+/// A service listed by `vsv avail`.
+struct AvailRow {
+    svc: RunitService,
+    status: &'static str,
+    style: Style,
+    package: Option<Package>,
+    source: PathBuf,
+    description: String,
+}
+
+impl AvailRow {
+    /// The owning package (and version) or "---".
+    fn pkgver(&self) -> &str {
+        self.package.as_ref().map_or("---", |pkg| &pkg.pkgver)
+    }
+
+    fn to_json(&self) -> String {
+        let package = match &self.package {
+            Some(pkg) => format!(
+                "{{\"name\":{},\"version\":{}}}",
+                utils::json_string(&pkg.name),
+                utils::json_string(pkg.version())
+            ),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"name\":{},\"status\":{},\"source\":{},\"description\":{},\"package\":{}}}",
+            utils::json_string(&self.svc.name),
+            utils::json_string(self.status),
+            utils::json_string(&self.source.display().to_string()),
+            utils::json_string(&self.description),
+            package
+        )
+    }
+}
+
+/**
+ * Fuzzy match a search against a service: every word of the search has to be
+ * in the name or description, or its letters have to appear in order in the
+ * name (so "ntp" matches "openntpd" and "nmgr" matches "NetworkManager").
+ */
+fn fuzzy_match(search: &str, name: &str, description: &str) -> bool {
+    let name = name.to_lowercase();
+    let description = description.to_lowercase();
+
+    search.to_lowercase().split_whitespace().all(|word| {
+        if name.contains(word) || description.contains(word) {
+            return true;
+        }
+
+        let mut chars = name.chars();
+        word.chars().all(|c| chars.any(|n| n == c))
+    })
+}
@@ -0,0 +1,72 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv audit`.
+
+use anyhow::Result;
+use yansi::Paint;
+
+use crate::audit;
+use crate::config::Config;
+use crate::utils::verbose;
+
+/// Handle `vsv audit`.
+pub fn do_audit(cfg: &Config, user: Option<&str>) -> Result<()> {
+    let service = cfg.operands.first();
+
+    verbose!(cfg, "reading audit log {:?}", cfg.audit_log);
+
+    if !cfg.audit_log.exists() {
+        println!("{}", format!("no audit log at {:?}", cfg.audit_log).dim());
+        return Ok(());
+    }
+
+    let records: Vec<_> = audit::read_records(cfg)?
+        .into_iter()
+        .filter(|rec| service.is_none_or(|svc| &rec.service == svc))
+        .filter(|rec| user.is_none_or(|user| rec.matches_user(user)))
+        .collect();
+
+    let name_width =
+        records.iter().map(|r| r.service.len()).max().unwrap_or(0).max(20);
+
+    println!(
+        "{}",
+        format!(
+            "{: <20} {: <12} {: <12} {: <10} {: <width$} {: <10} {}",
+            "TIME",
+            "USER",
+            "SUDO_USER",
+            "COMMAND",
+            "SERVICE",
+            "PREVIOUS",
+            "OUTCOME",
+            width = name_width
+        )
+        .bold()
+    );
+
+    for rec in records {
+        let outcome = if rec.succeeded() {
+            rec.outcome.green()
+        } else {
+            rec.outcome.red()
+        };
+
+        println!(
+            "{} {: <12} {: <12} {: <10} {} {: <10} {}",
+            format!("{: <20}", rec.timestamp).dim(),
+            rec.username(),
+            rec.sudo_user.as_deref().unwrap_or("-"),
+            rec.command,
+            format!("{: <width$}", rec.service, width = name_width).bold(),
+            rec.previous,
+            outcome,
+        );
+    }
+
+    Ok(())
+}
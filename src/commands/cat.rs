@@ -0,0 +1,252 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv cat` (print every file defining a service).
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use yansi::{Color, Paint, Style};
+
+use crate::commands::env;
+use crate::config::Config;
+
+/// Files defining a service, in the order they are printed, and whether
+/// each one is executed (and should be executable).
+const FILES: &[(&str, bool)] = &[
+    ("run", true),
+    ("finish", true),
+    ("check", true),
+    ("conf", false),
+    ("log/run", true),
+    ("log/finish", true),
+    ("log/conf", false),
+];
+
+/// Shell words to highlight.
+const KEYWORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "for", "while", "until", "do", "done",
+    "case", "esac", "in", "exec", "export", "return", "exit", "set", "[", "]",
+];
+
+/// Handle `vsv cat [--reveal] <svc>`.
+pub fn do_cat(cfg: &Config, name: &str, reveal: bool) -> Result<()> {
+    let dir = cfg.find_definition(name)?;
+
+    let mut files: Vec<(PathBuf, bool)> =
+        FILES.iter().map(|(file, script)| (dir.join(file), *script)).collect();
+    files
+        .extend(list_dir(&dir.join("actions"))?.into_iter().map(|p| (p, true)));
+
+    // env files are printed last, and are never expected to be executable
+    let envdir = dir.join("env");
+    files.extend(list_dir(&envdir)?.into_iter().map(|p| (p, false)));
+
+    let mut first = true;
+    for (path, script) in files {
+        if !path.is_file() {
+            continue;
+        }
+
+        if !first {
+            println!();
+        }
+        first = false;
+
+        // secret env values are masked like `vsv env` does
+        let is_env = path.starts_with(&envdir);
+        let masked = is_env
+            && !reveal
+            && path
+                .file_name()
+                .is_some_and(|name| env::is_secret(&name.to_string_lossy()));
+
+        print_file(cfg, &path, script, is_env, masked)?;
+    }
+
+    if first {
+        println!("{}", format!("no files found in {}", dir.display()).dim());
+    }
+
+    Ok(())
+}
+
+/// List the regular files in a directory (sorted), if it exists.
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to read dir {:?}", dir))?
+    {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Print a single file with a header (its real path and mode), replacing the
+/// content with a mask if `masked`.
+fn print_file(
+    cfg: &Config,
+    path: &Path,
+    script: bool,
+    is_env: bool,
+    masked: bool,
+) -> Result<()> {
+    let real = cfg
+        .canonicalize(path)
+        .with_context(|| format!("failed to resolve {:?}", path))?;
+    let mode = fs::metadata(&real)
+        .with_context(|| format!("failed to stat {:?}", real))?
+        .permissions()
+        .mode()
+        & 0o7777;
+
+    let header = format!("==> {} ({:04o}) <==", real.display(), mode);
+    print!("{}", header.bold());
+    if script && mode & 0o111 == 0 {
+        print!(" {}", "not executable!".red());
+    }
+    println!();
+
+    if masked {
+        println!("{}", "********".dim());
+        return Ok(());
+    }
+
+    let mut content = fs::read(&real)
+        .with_context(|| format!("failed to read {:?}", real))?;
+
+    // envdir files store newlines as NUL bytes
+    if is_env {
+        content.iter_mut().filter(|b| **b == 0).for_each(|b| *b = b'\n');
+    }
+
+    let text = match String::from_utf8(content) {
+        Ok(text) if !text.contains('\0') => text,
+        _ => {
+            println!("{}", "(binary file, skipped)".dim());
+            return Ok(());
+        }
+    };
+
+    let first_line = text.lines().next().unwrap_or("");
+    let is_conf = real.file_name().is_some_and(|name| name == "conf");
+    let shell = !is_env
+        && (is_conf
+            || first_line.starts_with("#!") && first_line.contains("sh"));
+
+    for line in text.lines() {
+        if shell && cfg.colorize {
+            println!("{}", highlight_shell(line));
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Highlight a single line of shell: comments, quoted strings, variables, and
+ * keywords.  This is deliberately simple (no multi-line strings or heredocs).
+ */
+fn highlight_shell(line: &str) -> String {
+    let comment = Style::default().dim();
+    let string = Style::default().fg(Color::Yellow);
+    let variable = Style::default().fg(Color::Cyan);
+    let keyword = Style::default().fg(Color::Magenta).bold();
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        match c {
+            '#' if i == 0 || chars[i - 1].is_whitespace() => {
+                let s: String = chars[i..].iter().collect();
+                out.push_str(&s.paint(comment).to_string());
+                break;
+            }
+            '\'' | '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    if c == '"' && chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                let s: String = chars[start..i].iter().collect();
+                out.push_str(&s.paint(string).to_string());
+            }
+            '$' => {
+                i += 1;
+                if i < chars.len() && chars[i] == '{' {
+                    while i < chars.len() && chars[i] != '}' {
+                        i += 1;
+                    }
+                    i = (i + 1).min(chars.len());
+                } else if i < chars.len()
+                    && (chars[i].is_ascii_digit()
+                        || "@*#?$!-".contains(chars[i]))
+                {
+                    i += 1;
+                } else {
+                    while i < chars.len()
+                        && (chars[i].is_ascii_alphanumeric() || chars[i] == '_')
+                    {
+                        i += 1;
+                    }
+                }
+                let s: String = chars[start..i].iter().collect();
+                out.push_str(&s.paint(variable).to_string());
+            }
+            c if c.is_whitespace() || ";|&()<>".contains(c) => {
+                // `&&` and `||` are highlighted like keywords
+                if (c == '&' || c == '|') && chars.get(i + 1) == Some(&c) {
+                    let s: String = chars[i..i + 2].iter().collect();
+                    out.push_str(&s.paint(keyword).to_string());
+                    i += 2;
+                    continue;
+                }
+                out.push(c);
+                i += 1;
+            }
+            _ => {
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !";|&()<>'\"$".contains(chars[i])
+                {
+                    i += 1;
+                }
+                let s: String = chars[start..i].iter().collect();
+                if KEYWORDS.contains(&s.as_str()) {
+                    out.push_str(&s.paint(keyword).to_string());
+                } else {
+                    out.push_str(&s);
+                }
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,87 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv check` subcommand.
+
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+use yansi::Paint;
+
+use crate::config::Config;
+use crate::health::{self, Health, HealthCache};
+use crate::runit::{self, RunitService};
+use crate::utils::verbose;
+
+/// Handle `vsv check [services...]`.
+pub fn do_check(cfg: &Config) -> Result<()> {
+    // default to every service in the service directory
+    let services: Vec<RunitService> = if cfg.operands.is_empty() {
+        runit::get_services(
+            &cfg.svdir,
+            false,
+            None::<&str>,
+            cfg.root.as_deref(),
+        )
+        .with_context(|| format!("failed to list services in {:?}", cfg.svdir))?
+        .into_iter()
+        .filter(|svc| !cfg.is_broken_link(&svc.path))
+        .map(|svc| {
+            RunitService::new(&svc.name, &cfg.resolve_service(&svc.path))
+        })
+        .collect()
+    } else {
+        cfg.operands
+            .iter()
+            .map(|name| Ok(RunitService::new(name, &cfg.service_path(name)?)))
+            .collect::<Result<_>>()?
+    };
+
+    // run every check in parallel, the results are printed in order below
+    let results: Vec<Option<Health>> = services
+        .par_iter()
+        .map(|svc| {
+            if !svc.path.is_dir() {
+                return None;
+            }
+
+            Some(health::run_check(svc, cfg.check_timeout))
+        })
+        .collect();
+
+    let mut cache = HealthCache::load(&cfg.health_cache, cfg.health_ttl);
+    let mut unhealthy = 0;
+
+    for (svc, health) in services.iter().zip(results) {
+        print!("checking service {}... ", svc.name.bold());
+
+        let Some(health) = health else {
+            println!("{}", "failed! service not found".red());
+            unhealthy += 1;
+            continue;
+        };
+
+        let s = match &health {
+            Health::Unhealthy(reason) => format!("{} ({})", health, reason),
+            _ => health.to_string(),
+        };
+        println!("{}", s.paint(health.style()));
+
+        if matches!(health, Health::Unhealthy(_)) {
+            unhealthy += 1;
+        }
+        cache.insert(svc, &health);
+    }
+
+    if let Err(err) = cache.save() {
+        verbose!(cfg, "failed to save health cache: {}", err);
+    }
+
+    if unhealthy > 0 {
+        bail!("{} service(s) unhealthy", unhealthy);
+    }
+
+    Ok(())
+}
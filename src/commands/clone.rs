@@ -0,0 +1,163 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv clone` (copy a service definition under a new name).
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::Paint;
+
+use crate::audit;
+use crate::commands::add_remove;
+use crate::config::Config;
+
+/// Handle `vsv clone <src> <dst>`.
+pub fn do_clone(cfg: &Config, src: &str, dst: &str, add: bool) -> Result<()> {
+    ensure!(
+        !dst.is_empty()
+            && !dst.contains('/')
+            && !dst.starts_with('.')
+            && dst != "log",
+        "invalid service name: {:?}",
+        dst
+    );
+
+    let source = cfg.find_avail(src).with_context(|| {
+        format!("{} not found in avail dirs ({})", src, cfg.avail_path_string())
+    })?;
+
+    // the clone lives next to the original
+    let avail_dir = source.parent().context("invalid source dir")?;
+    let dir = avail_dir.join(dst);
+    if fs::symlink_metadata(&dir).is_ok() {
+        bail!("{} already exists", dir.display());
+    }
+
+    print!("{} service {} to {}... ", "cloning".bold(), src.bold(), dst.bold());
+
+    // copy into a temp dir first so a partial copy never shows up
+    let tmp_dir = avail_dir.join(format!(".{}.vsv-clone", dst));
+    let ret = copy_tree(&source, &tmp_dir)
+        .and_then(|()| rewrite_log_tag(&tmp_dir, src, dst))
+        .and_then(|rewrote| {
+            fs::rename(&tmp_dir, &dir)?;
+            Ok(rewrote)
+        });
+    audit::record(cfg, "clone", dst, &format!("clone of {}", src), &ret);
+
+    let rewrote = match ret {
+        Ok(rewrote) => rewrote,
+        Err(err) => {
+            println!("{}", "failed".red());
+            let _ = fs::remove_dir_all(&tmp_dir);
+            return Err(err)
+                .with_context(|| format!("failed to create {:?}", dir));
+        }
+    };
+
+    println!("{}", "done".green());
+    println!("  {}", dir.display());
+    if rewrote {
+        println!("  {}", "rewrote the logger tag in log/run".dim());
+    }
+
+    if add {
+        add_remove::do_add(cfg, &[dst.to_string()], false, false)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Recursively copy a service directory, skipping runtime artifacts
+ * (`supervise`).  Symlinks are copied as symlinks, and file modes are kept.
+ */
+fn copy_tree(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir(dst)
+        .with_context(|| format!("failed to create {:?}", dst))?;
+    fs::set_permissions(dst, fs::metadata(src)?.permissions())?;
+
+    for entry in fs::read_dir(src)
+        .with_context(|| format!("failed to read dir {:?}", src))?
+    {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "supervise" {
+            continue;
+        }
+
+        let from = entry.path();
+        let to = dst.join(&name);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            symlink(fs::read_link(&from)?, &to)?;
+        } else if file_type.is_dir() {
+            copy_tree(&from, &to)?;
+        } else {
+            // fs::copy keeps the permissions
+            fs::copy(&from, &to)
+                .with_context(|| format!("failed to copy {:?}", from))?;
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Replace the old service name with the new one in `log/run` (the logger tag
+ * or log directory), returning whether anything changed.
+ *
+ * A `log/run` symlink (e.g. to vlogger, which uses the service name as the
+ * tag) is left alone.
+ */
+fn rewrite_log_tag(dir: &Path, src: &str, dst: &str) -> Result<bool> {
+    let log_run = dir.join("log").join("run");
+    match fs::symlink_metadata(&log_run) {
+        Ok(meta) if meta.file_type().is_file() => {}
+        _ => return Ok(false),
+    }
+
+    let content = fs::read_to_string(&log_run)
+        .with_context(|| format!("failed to read {:?}", log_run))?;
+    let new_content = replace_word(&content, src, dst);
+    if new_content == content {
+        return Ok(false);
+    }
+
+    fs::write(&log_run, new_content)
+        .with_context(|| format!("failed to write {:?}", log_run))?;
+
+    Ok(true)
+}
+
+/// Replace every occurrence of `from` that isn't part of a larger name.
+fn replace_word(s: &str, from: &str, to: &str) -> String {
+    let is_name_char =
+        |c: char| c.is_ascii_alphanumeric() || "-_.@".contains(c);
+
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(idx) = rest.find(from) {
+        let before =
+            rest[..idx].chars().next_back().or(out.chars().next_back());
+        let after = rest[idx + from.len()..].chars().next();
+
+        out.push_str(&rest[..idx]);
+        if before.is_some_and(is_name_char) || after.is_some_and(is_name_char) {
+            out.push_str(from);
+        } else {
+            out.push_str(to);
+        }
+        rest = &rest[idx + from.len()..];
+    }
+    out.push_str(rest);
+
+    out
+}
@@ -0,0 +1,288 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv conf` (view and edit a service's `conf` file).
+//!
+//! Most Void run scripts source `./conf` (`[ -r conf ] && . ./conf`) and use
+//! the variables it sets, like `OPTS`.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::Paint;
+
+use crate::arguments::ConfAction;
+use crate::audit;
+use crate::commands::control;
+use crate::config::Config;
+use crate::utils;
+
+/// Handle `vsv conf <svc> [set|unset|edit]`.
+pub fn do_conf(
+    cfg: &Config,
+    name: &str,
+    action: Option<&ConfAction>,
+    restart: bool,
+) -> Result<()> {
+    let dir = cfg.find_definition(name)?;
+    let path = dir.join("conf");
+
+    let Some(action) = action else {
+        return print_conf(&path);
+    };
+
+    let content = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read {:?}", path));
+        }
+    };
+
+    match action {
+        ConfAction::Set { assignments } => {
+            ensure!(!assignments.is_empty(), "at least one KEY=VALUE required");
+
+            let mut content = content;
+            for assignment in assignments {
+                let Some((key, value)) = assignment.split_once('=') else {
+                    bail!(
+                        "invalid assignment (expected KEY=VALUE): {}",
+                        assignment
+                    );
+                };
+                ensure!(
+                    utils::valid_var_name(key),
+                    "invalid variable name: {:?}",
+                    key
+                );
+
+                ensure!(
+                    !value.contains('\n'),
+                    "multi-line values aren't supported (use `vsv conf {} \
+                     edit`)",
+                    name
+                );
+                ensure_single_line(&content, key, name)?;
+
+                let previous = describe(&content, key);
+                content = set_var(&content, key, value);
+                let ret = write_conf(&path, &content);
+                audit::record(cfg, "conf set", name, &previous, &ret);
+                ret?;
+
+                println!("{} {}={}", "set".green(), key, quote(value));
+            }
+        }
+        ConfAction::Unset { keys } => {
+            ensure!(!keys.is_empty(), "at least one KEY required");
+
+            let mut content = content;
+            for key in keys {
+                ensure!(
+                    utils::valid_var_name(key),
+                    "invalid variable name: {:?}",
+                    key
+                );
+
+                ensure_single_line(&content, key, name)?;
+
+                let previous = describe(&content, key);
+                content = unset_var(&content, key);
+                let ret = write_conf(&path, &content);
+                audit::record(cfg, "conf unset", name, &previous, &ret);
+                ret?;
+
+                println!("{} {}", "unset".green(), key);
+            }
+        }
+        ConfAction::Edit => {
+            let ret = utils::run_editor(&path);
+            audit::record(cfg, "conf edit", name, "-", &ret);
+            ret?;
+        }
+    }
+
+    if restart {
+        control::restart_service(cfg, name)?;
+    }
+
+    Ok(())
+}
+
+/// Print the variables set by the conf file (the last assignment wins).
+fn print_conf(path: &Path) -> Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            println!("{}", format!("no conf file ({})", path.display()).dim());
+            return Ok(());
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read {:?}", path));
+        }
+    };
+
+    println!("{}", format!("# {}", path.display()).dim());
+
+    let mut vars: Vec<(&str, &str)> = vec![];
+    for line in content.lines() {
+        if let Some((key, value)) = parse_assignment(line) {
+            match vars.iter_mut().find(|(k, _)| *k == key) {
+                Some(var) => var.1 = value,
+                None => vars.push((key, value)),
+            }
+        }
+    }
+
+    for (key, value) in vars {
+        println!("{}={}", key.bold(), value);
+    }
+
+    Ok(())
+}
+
+/// Write the conf file atomically, keeping its mode.
+fn write_conf(path: &Path, content: &str) -> Result<()> {
+    let mode = fs::metadata(path)
+        .map(|meta| meta.permissions().mode() & 0o7777)
+        .unwrap_or(0o644);
+
+    utils::write_atomic(path, content.as_bytes(), mode)
+}
+
+/// Describe the current state of a variable (for the audit log, no values).
+fn describe(content: &str, key: &str) -> String {
+    let set =
+        content.lines().filter_map(parse_assignment).any(|(k, _)| k == key);
+
+    format!("{} {}", key, if set { "set" } else { "unset" })
+}
+
+/**
+ * Make sure every assignment of a variable is on a single line: a quoted
+ * value can span multiple lines, which `set` and `unset` can't safely
+ * rewrite.
+ */
+fn ensure_single_line(content: &str, key: &str, name: &str) -> Result<()> {
+    for (i, line) in content.lines().enumerate() {
+        if let Some((k, value)) = parse_assignment(line) {
+            ensure!(
+                k != key || is_terminated(value),
+                "line {}: {} spans multiple lines (use `vsv conf {} edit`)",
+                i + 1,
+                key,
+                name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a shell value ends on its own line (no open quote or trailing
+/// backslash).
+fn is_terminated(value: &str) -> bool {
+    let mut quote = None;
+    let mut prev = '=';
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => (),
+            // skips the escaped character (a trailing one continues the line)
+            (_, '\\') if chars.next().is_none() => return false,
+            (Some('"'), '"') => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') if prev.is_whitespace() => break,
+            _ => (),
+        }
+        prev = c;
+    }
+
+    quote.is_none()
+}
+
+/**
+ * Set a variable: the first assignment of the variable is replaced (and any
+ * later ones are removed), or a new line is appended.  Comments and all other
+ * lines are kept as they are.
+ */
+fn set_var(content: &str, key: &str, value: &str) -> String {
+    let new_line = format!("{}={}", key, quote(value));
+    let mut lines = vec![];
+    let mut replaced = false;
+
+    for line in content.lines() {
+        match parse_assignment(line) {
+            Some((k, _)) if k == key => {
+                if !replaced {
+                    lines.push(new_line.clone());
+                    replaced = true;
+                }
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    if !replaced {
+        lines.push(new_line);
+    }
+
+    let mut s = lines.join("\n");
+    s.push('\n');
+    s
+}
+
+/// Remove every assignment of a variable, keeping everything else.
+fn unset_var(content: &str, key: &str) -> String {
+    let mut s = String::new();
+    for line in content.lines() {
+        if matches!(parse_assignment(line), Some((k, _)) if k == key) {
+            continue;
+        }
+        s.push_str(line);
+        s.push('\n');
+    }
+    s
+}
+
+/// Parse a `KEY=VALUE` (or `export KEY=VALUE`) line, ignoring comments.
+fn parse_assignment(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+    let (key, value) = line.split_once('=')?;
+
+    utils::valid_var_name(key).then_some((key, value))
+}
+
+/// Quote a value for the shell (double quotes, only if needed).
+fn quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+
+    if safe {
+        return value.to_string();
+    }
+
+    let mut s = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s.push('"');
+    s
+}
@@ -6,84 +6,129 @@
 
 //! `vsv` control commands (start, stop, etc.).
 
+use std::io;
+use std::io::Write;
+
 use anyhow::{ensure, Result};
 use yansi::Paint;
 
 use crate::arguments::Commands;
+use crate::audit;
 use crate::config::Config;
+use crate::journal::Recorder;
 use crate::runit::{RunitCommand, RunitService};
 
 /// Handle all control subcommands.
 pub fn run(cfg: &Config, cmd: &Commands) -> Result<()> {
     // Determine the action and the service list
-    let (services, command, verb) = match cmd {
+    let (services, command, cmd_name, verb) = match cmd {
         Commands::Start { services } => {
-            (services, Some(RunitCommand::Up), "starting")
+            (services, Some(RunitCommand::Up), "start", "starting")
         }
         Commands::Stop { services } => {
-            (services, Some(RunitCommand::Down), "stopping")
+            (services, Some(RunitCommand::Down), "stop", "stopping")
         }
         // Restart sends Term, Continue, Up (handled below)
-        Commands::Restart { services } => (services, None, "restarting"),
+        Commands::Restart { services } => {
+            (services, None, "restart", "restarting")
+        }
         Commands::Reload { services } => {
-            (services, Some(RunitCommand::Hup), "reloading")
+            (services, Some(RunitCommand::Hup), "reload", "reloading")
         }
         Commands::Once { services } => {
-            (services, Some(RunitCommand::Once), "running once")
+            (services, Some(RunitCommand::Once), "once", "running once")
         }
         Commands::Pause { services } => {
-            (services, Some(RunitCommand::Pause), "pausing")
+            (services, Some(RunitCommand::Pause), "pause", "pausing")
         }
         Commands::Cont { services } => {
-            (services, Some(RunitCommand::Cont), "resuming")
+            (services, Some(RunitCommand::Cont), "cont", "resuming")
         }
         Commands::Hup { services } => {
-            (services, Some(RunitCommand::Hup), "sending HUP")
+            (services, Some(RunitCommand::Hup), "hup", "sending HUP")
         }
         Commands::Alarm { services } => {
-            (services, Some(RunitCommand::Alarm), "sending ALARM")
-        }
-        Commands::Interrupt { services } => {
-            (services, Some(RunitCommand::Interrupt), "sending INT")
+            (services, Some(RunitCommand::Alarm), "alarm", "sending ALARM")
         }
+        Commands::Interrupt { services } => (
+            services,
+            Some(RunitCommand::Interrupt),
+            "interrupt",
+            "sending INT",
+        ),
         Commands::Quit { services } => {
-            (services, Some(RunitCommand::Quit), "sending QUIT")
+            (services, Some(RunitCommand::Quit), "quit", "sending QUIT")
         }
         Commands::Term { services } => {
-            (services, Some(RunitCommand::Term), "sending TERM")
+            (services, Some(RunitCommand::Term), "term", "sending TERM")
         }
         Commands::Kill { services } => {
-            (services, Some(RunitCommand::Kill), "sending KILL")
+            (services, Some(RunitCommand::Kill), "kill", "sending KILL")
         }
         Commands::Exit { services } => {
-            (services, Some(RunitCommand::Exit), "exiting")
+            (services, Some(RunitCommand::Exit), "exit", "exiting")
         }
         _ => return Ok(()), // Should not happen given the dispatch in main
     };
 
     ensure!(!services.is_empty(), "at least one (1) service required");
 
-    for name in services {
-        let p = cfg.svdir.join(name);
-        let svc = RunitService::new(name, &p);
+    // only changes to the wanted up/down state can be undone
+    let recorder = Recorder::begin(cfg, cmd_name);
+    let changes_want = matches!(
+        command,
+        None | Some(RunitCommand::Up | RunitCommand::Down | RunitCommand::Once)
+    );
 
+    for name in services {
         print!("{} service {}... ", verb, name.bold());
 
+        let svc = match cfg.service_path(name) {
+            Ok(path) => RunitService::new(name, &path),
+            Err(err) => {
+                println!("{}: {}", "failed".red(), err);
+                continue;
+            }
+        };
+
+        // a custom `reload` action takes precedence over sending HUP
+        if cmd_name == "reload" && svc.has_action("reload") {
+            io::stdout().flush()?;
+            let previous = svc.state_string();
+            let result = svc.run_action("reload", &[]);
+            audit::record(cfg, "action reload", name, &previous, &result);
+
+            match result {
+                Ok(_) => println!("{}", "ok".green()),
+                Err(e) => println!("{}: {}", "failed".red(), e),
+            }
+            continue;
+        }
+
         if !svc.valid() {
             println!("{}", "failed! service not valid".red());
             continue;
         }
 
+        let previous = svc.state_string();
+        if changes_want {
+            recorder.snapshot_want(&svc);
+        }
+
         let result = if let Some(c) = command {
             // Standard single command
             svc.control(c)
         } else {
             // Restart sequence: Terminate -> Continue -> Up
-            svc.control(RunitCommand::Term)
-                .and_then(|_| svc.control(RunitCommand::Cont))
-                .and_then(|_| svc.control(RunitCommand::Up))
+            svc.control_all(&[
+                RunitCommand::Term,
+                RunitCommand::Cont,
+                RunitCommand::Up,
+            ])
         };
 
+        audit::record(cfg, cmd_name, name, &previous, &result);
+
         match result {
             Ok(_) => println!("{}", "ok".green()),
             Err(e) => println!("{}: {}", "failed".red(), e),
@@ -92,3 +137,33 @@ pub fn run(cfg: &Config, cmd: &Commands) -> Result<()> {
 
     Ok(())
 }
+
+/**
+ * Restart a single service (`vsv restart`), used to apply changes made by
+ * other commands.
+ */
+pub fn restart_service(cfg: &Config, name: &str) -> Result<()> {
+    let svc = RunitService::new(name, &cfg.service_path(name)?);
+
+    ensure!(svc.valid(), "service {} not valid (not added?)", name);
+    print!("restarting service {}... ", name.bold());
+
+    let previous = svc.state_string();
+    let ret = svc.control_all(&[
+        RunitCommand::Term,
+        RunitCommand::Cont,
+        RunitCommand::Up,
+    ]);
+    audit::record(cfg, "restart", name, &previous, &ret);
+
+    match ret {
+        Ok(()) => {
+            println!("{}", "ok".green());
+            Ok(())
+        }
+        Err(err) => {
+            println!("{}", "failed".red());
+            Err(err)
+        }
+    }
+}
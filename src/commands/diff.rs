@@ -0,0 +1,181 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv diff` (compare a service against the files its package installed).
+//!
+//! Local changes to files installed by a package conflict with the next
+//! update of the package, so it's worth knowing which services have them.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::{Color, Paint, Style};
+
+use crate::config::Config;
+use crate::sha256;
+use crate::xbps::{PackageFile, PkgDb};
+
+/// Local files that aren't part of the service definition.
+const IGNORED: &[&str] = &["supervise", "down"];
+
+/// How a file differs from the installed package.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Change {
+    Modified,
+    Added,
+    Deleted,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Change::Modified => "modified",
+            Change::Added => "added",
+            Change::Deleted => "deleted",
+        };
+
+        s.fmt(f)
+    }
+}
+
+impl Change {
+    fn style(&self) -> Style {
+        let style = Style::default();
+        match self {
+            Change::Modified => style.fg(Color::Yellow),
+            Change::Added => style.fg(Color::Green),
+            Change::Deleted => style.fg(Color::Red),
+        }
+    }
+}
+
+/// Handle `vsv diff <svc...>`.
+pub fn do_diff(cfg: &Config) -> Result<()> {
+    ensure!(!cfg.operands.is_empty(), "at least one (1) service required");
+
+    let pkgdb = PkgDb::load(&cfg.xbps_dbdir, cfg.root.as_deref());
+
+    for (i, name) in cfg.operands.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let dir = cfg.find_definition(name)?;
+        let Some(pkg) = pkgdb.service_owner(&dir) else {
+            bail!(
+                "{} ({}) was not installed by an xbps package",
+                name,
+                dir.display()
+            );
+        };
+
+        println!(
+            "{} {} ({}) {}",
+            "service".bold(),
+            name.bold(),
+            pkg.pkgver.blue(),
+            dir.display().dim()
+        );
+
+        let changes = service_changes(&pkgdb, &pkg.name, &dir)?;
+        if changes.is_empty() {
+            println!("  {}", "no local changes".green());
+            continue;
+        }
+
+        for (change, file) in changes {
+            let label = format!("{: <9}", change.to_string());
+            println!("  {} {}", label.paint(change.style()), file.display());
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Compare a service directory against the files installed by its package,
+ * returning the changed files (relative to the service directory).
+ */
+pub fn service_changes(
+    pkgdb: &PkgDb,
+    pkgname: &str,
+    dir: &Path,
+) -> Result<Vec<(Change, PathBuf)>> {
+    let files = pkgdb.files_in(pkgname, dir);
+    let mut changes = vec![];
+
+    for file in &files {
+        if let Some(change) = file_change(file)? {
+            changes.push((change, relative(&file.path, dir)));
+        }
+    }
+
+    let known: HashSet<&Path> =
+        files.iter().map(|f| f.path.as_path()).collect();
+    let mut local = vec![];
+    list_files(dir, &mut local)?;
+    for path in local {
+        if !known.contains(path.as_path()) {
+            changes.push((Change::Added, relative(&path, dir)));
+        }
+    }
+
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+
+    Ok(changes)
+}
+
+/// Check a single packaged file against what's on disk.
+fn file_change(file: &PackageFile) -> Result<Option<Change>> {
+    let Ok(meta) = fs::symlink_metadata(&file.path) else {
+        return Ok(Some(Change::Deleted));
+    };
+
+    if let Some(target) = &file.target {
+        let same = meta.file_type().is_symlink()
+            && fs::read_link(&file.path)? == Path::new(target);
+        return Ok((!same).then_some(Change::Modified));
+    }
+
+    let Some(sha256) = &file.sha256 else {
+        return Ok(None);
+    };
+
+    let content = fs::read(&file.path)
+        .with_context(|| format!("failed to read {:?}", file.path))?;
+    let same = sha256::hex_digest(&content).eq_ignore_ascii_case(sha256);
+
+    Ok((!same).then_some(Change::Modified))
+}
+
+/// Recursively list every file and symlink in a service directory.
+fn list_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to read dir {:?}", dir))?
+    {
+        let entry = entry?;
+        if IGNORED.iter().any(|name| entry.file_name() == *name) {
+            continue;
+        }
+
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            list_files(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Get a path relative to the service directory.
+fn relative(path: &Path, dir: &Path) -> PathBuf {
+    path.strip_prefix(dir).unwrap_or(path).to_path_buf()
+}
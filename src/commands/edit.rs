@@ -0,0 +1,187 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv edit` (safely edit a file defining a service).
+//!
+//! The file is edited as a temporary copy and only written back (atomically)
+//! once it passes a syntax check, so a half-written or broken run script is
+//! never picked up by runsv.
+
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path};
+use std::process::Command;
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::Paint;
+
+use crate::audit;
+use crate::commands::control;
+use crate::config::Config;
+use crate::runit::RunitService;
+use crate::utils;
+use crate::xbps::PkgDb;
+
+/// Files that are executed by runsv (or vsv), and created executable.
+const SCRIPTS: &[&str] = &["run", "finish", "check", "log/run", "log/finish"];
+
+/// Handle `vsv edit <svc> [file]`.
+pub fn do_edit(
+    cfg: &Config,
+    name: &str,
+    file: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    let file = file.unwrap_or("run");
+    let relative = Path::new(file);
+    ensure!(
+        !file.is_empty()
+            && relative.components().all(|c| matches!(c, Component::Normal(_))),
+        "invalid file name (must be relative to the service dir): {:?}",
+        file
+    );
+
+    let dir = cfg.find_definition(name)?;
+    let path = dir.join(relative);
+
+    // edit the real file if it's a symlink
+    let path = cfg.canonicalize(&path).unwrap_or(path);
+    ensure!(!path.is_dir(), "{} is a directory", path.display());
+
+    let pkgdb = PkgDb::load(&cfg.xbps_dbdir, cfg.root.as_deref());
+    if let Some(pkg) = pkgdb.owner(&path) {
+        if !force {
+            bail!(
+                "{} is owned by package {} and will be overwritten when the \
+                 package is updated (use --force to edit it anyway)",
+                path.display(),
+                pkg.name
+            );
+        }
+        println!(
+            "{}: {} is owned by package {}, changes will be overwritten when \
+             the package is updated",
+            "warning".yellow(),
+            path.display(),
+            pkg.name
+        );
+    }
+
+    let (original, mode) = match fs::metadata(&path) {
+        Ok(meta) => {
+            let content = fs::read(&path)
+                .with_context(|| format!("failed to read {:?}", path))?;
+            (content, meta.permissions().mode() & 0o7777)
+        }
+        Err(_) if SCRIPTS.contains(&file) => (vec![], 0o755),
+        Err(_) => (vec![], 0o644),
+    };
+
+    // the scratch copy goes in a private dir so nobody else can swap it out
+    // (or put a symlink in its place) while we're running as root
+    let tmp_dir = utils::make_temp_dir("vsv-edit")?;
+    let tmp = tmp_dir.join(format!("{}.{}", name, file.replace('/', "-")));
+
+    let ret = write_scratch(&tmp, &original)
+        .and_then(|()| edit_until_valid(&tmp, file, &original));
+    let _ = fs::remove_dir_all(&tmp_dir);
+
+    let Some(content) = ret? else {
+        println!("no changes made to {}", path.display());
+        return Ok(());
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let ret = utils::write_atomic(&path, &content, mode);
+    audit::record(cfg, &format!("edit {}", file), name, "-", &ret);
+    ret?;
+
+    println!("{} {}", "wrote".green(), path.display());
+
+    let svc = RunitService::new(name, &cfg.service_path(name)?);
+    if svc.valid()
+        && utils::confirm(&format!("restart service {} now?", name.bold()))?
+    {
+        control::restart_service(cfg, name)?;
+    }
+
+    Ok(())
+}
+
+/// Write the scratch copy of the file (which must not exist yet).
+fn write_scratch(tmp: &Path, content: &[u8]) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(tmp)
+        .with_context(|| format!("failed to create {:?}", tmp))?;
+    f.write_all(content)?;
+
+    Ok(())
+}
+
+/**
+ * Open the editor until the file is unchanged (`None`) or passes the syntax
+ * check (`Some(content)`), asking to edit again if it fails.
+ */
+fn edit_until_valid(
+    tmp: &Path,
+    file: &str,
+    original: &[u8],
+) -> Result<Option<Vec<u8>>> {
+    loop {
+        utils::run_editor(tmp)?;
+
+        let content = fs::read(tmp)
+            .with_context(|| format!("failed to read {:?}", tmp))?;
+        if content == original {
+            return Ok(None);
+        }
+
+        if !is_shell(file, &content) {
+            return Ok(Some(content));
+        }
+
+        let output = Command::new("sh")
+            .arg("-n")
+            .arg(tmp)
+            .output()
+            .context("failed to run sh -n")?;
+        if output.status.success() {
+            return Ok(Some(content));
+        }
+
+        println!("{}: {} has syntax errors:", "error".red(), file);
+        print!("{}", String::from_utf8_lossy(&output.stderr));
+
+        if !utils::confirm("edit again?")? {
+            bail!("{} not changed (syntax errors)", file);
+        }
+    }
+}
+
+/// Check if the file is a shell script (or sourced by one).
+fn is_shell(file: &str, content: &[u8]) -> bool {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    if name == "conf" {
+        return true;
+    }
+
+    let first_line = content.split(|b| *b == b'\n').next().unwrap_or(&[]);
+    let first_line = String::from_utf8_lossy(first_line);
+
+    if first_line.starts_with("#!") {
+        first_line.contains("sh")
+    } else {
+        SCRIPTS.contains(&file)
+    }
+}
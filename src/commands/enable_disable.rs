@@ -9,9 +9,12 @@
 use anyhow::{ensure, Result};
 use yansi::Paint;
 
+use crate::audit;
 use crate::config;
 use crate::config::Config;
-use crate::runit::RunitService;
+use crate::journal::Recorder;
+use crate::runit::{RunitCommand, RunitService, RunitServiceState};
+use crate::utils;
 
 /// Handle `vsv enable`.
 pub fn do_enable(cfg: &Config) -> Result<()> {
@@ -28,34 +31,92 @@ fn _do_enable_disable(cfg: &Config) -> Result<()> {
     ensure!(!cfg.operands.is_empty(), "at least one (1) service required");
 
     let mut had_error = false;
+    let recorder = Recorder::begin(cfg, &cfg.mode.to_string());
 
     for name in &cfg.operands {
-        let p = cfg.svdir.join(name);
-        let svc = RunitService::new(name, &p);
         print!("{} service {}... ", cfg.mode, name.bold(),);
 
+        let svc = match cfg.service_path(name) {
+            Ok(path) => RunitService::new(name, &path),
+            Err(err) => {
+                println!("{}", format!("failed! {}", err).red());
+                had_error = true;
+                continue;
+            }
+        };
+
         if !svc.valid() {
             println!("{}", "failed! service not valid".red());
             had_error = true;
             continue;
         }
 
-        let ret = match cfg.mode {
-            config::ProgramMode::Enable => svc.enable(),
-            config::ProgramMode::Disable => svc.disable(),
-            _ => unreachable!(),
-        };
+        let previous = if svc.enabled() { "enabled" } else { "disabled" };
+        recorder.snapshot_enabled(&svc);
+        if cfg.now {
+            recorder.snapshot_want(&svc);
+        }
 
-        match ret {
-            Err(err) => {
-                had_error = true;
-                println!("{}", format!("failed! {}", err).red());
-            }
-            Ok(()) => println!("{}.", "done".green()),
-        };
+        let enable = matches!(cfg.mode, config::ProgramMode::Enable);
+        let ret = if enable { svc.enable() } else { svc.disable() };
+
+        audit::record(cfg, &cfg.mode.to_string(), name, previous, &ret);
+
+        if let Err(err) = ret {
+            had_error = true;
+            println!("{}", format!("failed! {}", err).red());
+            continue;
+        }
+
+        if !cfg.now {
+            println!("{}.", "done".green());
+            continue;
+        }
+
+        // report the boot-time (down file) and runtime results separately
+        let boot = if enable { "enabled" } else { "disabled" };
+        print!("{}, ", format!("{} at boot", boot).green());
+        if let Err(err) = start_stop(cfg, &svc, enable) {
+            had_error = true;
+            println!("{}", format!("failed! {}", err).red());
+        }
     }
 
     ensure!(!had_error, "failed to modify service(s)");
 
     Ok(())
 }
+
+/// Start (`up`) or stop (`down`) the service for `--now`, optionally waiting
+/// for the state to change.
+fn start_stop(cfg: &Config, svc: &RunitService, up: bool) -> Result<()> {
+    let (cmd_name, command, done, want_state) = if up {
+        ("start", RunitCommand::Up, "started", RunitServiceState::Run)
+    } else {
+        ("stop", RunitCommand::Down, "stopped", RunitServiceState::Down)
+    };
+
+    let previous = svc.state_string();
+    let ret = svc.control(command);
+    audit::record(cfg, cmd_name, &svc.name, &previous, &ret);
+    ret?;
+
+    if !cfg.wait {
+        println!("{}.", done.green());
+        return Ok(());
+    }
+
+    let reached = utils::wait_until(cfg.wait_timeout, || {
+        svc.get_status().is_ok_and(|status| status.state == want_state)
+    });
+    ensure!(
+        reached,
+        "timed out waiting for state {} after {}s",
+        want_state,
+        cfg.wait_timeout.as_secs()
+    );
+
+    println!("{}", format!("{} ({}).", done, want_state).green());
+
+    Ok(())
+}
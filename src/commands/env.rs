@@ -0,0 +1,192 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv env` (manage the `chpst -e` envdir of a service).
+//!
+//! An envdir has one file per variable: the first line of the file is the
+//! value, and an empty file removes the variable from the environment.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::Paint;
+
+use crate::arguments::EnvAction;
+use crate::audit;
+use crate::config::Config;
+use crate::runit::RunitService;
+use crate::utils;
+
+/// Variables with names matching these patterns have their values masked.
+const SECRET_PATTERNS: &[&str] =
+    &["*PASSWORD*", "*PASSWD*", "*SECRET*", "*TOKEN*", "*CREDENTIAL*", "*_KEY"];
+
+/// Handle `vsv env <svc> [set|unset]`.
+pub fn do_env(
+    cfg: &Config,
+    name: &str,
+    action: Option<&EnvAction>,
+    reveal: bool,
+) -> Result<()> {
+    let path = cfg.find_definition(name)?;
+    let svc = RunitService::new(name, &path);
+
+    // fall back to `env/` so variables can be set before the run script
+    // is changed to use them
+    let used = svc.envdir();
+    let dir = used.clone().unwrap_or_else(|| path.join("env"));
+
+    if used.is_none() {
+        println!(
+            "{}: run script for {} doesn't use chpst -e, {} is not applied",
+            "warning".yellow(),
+            name.bold(),
+            dir.display()
+        );
+    }
+
+    let Some(action) = action else {
+        return print_env(&dir, reveal);
+    };
+
+    match action {
+        EnvAction::Set { assignments } => {
+            ensure!(!assignments.is_empty(), "at least one KEY=VALUE required");
+
+            if !dir.is_dir() {
+                fs::create_dir_all(&dir).with_context(|| {
+                    format!("failed to create dir {:?}", dir)
+                })?;
+            }
+
+            for assignment in assignments {
+                let Some((key, value)) = assignment.split_once('=') else {
+                    bail!(
+                        "invalid assignment (expected KEY=VALUE): {}",
+                        assignment
+                    );
+                };
+                ensure!(
+                    utils::valid_var_name(key),
+                    "invalid variable name: {:?}",
+                    key
+                );
+
+                let file = dir.join(key);
+                let previous = describe(&file);
+                let ret = write_var(&file, key, value);
+                audit::record(cfg, "env set", name, &previous, &ret);
+                ret?;
+
+                println!("{} {}", "set".green(), key);
+            }
+        }
+        EnvAction::Unset { keys } => {
+            ensure!(!keys.is_empty(), "at least one KEY required");
+
+            for key in keys {
+                ensure!(
+                    utils::valid_var_name(key),
+                    "invalid variable name: {:?}",
+                    key
+                );
+
+                let file = dir.join(key);
+                let previous = describe(&file);
+                let ret = match fs::remove_file(&file) {
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                    ret => ret,
+                };
+                audit::record(cfg, "env unset", name, &previous, &ret);
+                ret.with_context(|| format!("failed to remove {:?}", file))?;
+
+                println!("{} {}", "unset".green(), key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print every variable in the envdir, masking secrets unless `reveal`.
+fn print_env(dir: &Path, reveal: bool) -> Result<()> {
+    if !dir.is_dir() {
+        println!("{}", format!("no envdir ({})", dir.display()).dim());
+        return Ok(());
+    }
+
+    println!("{}", format!("# {}", dir.display()).dim());
+
+    for (key, value) in utils::read_envdir(dir)? {
+        let value = match value {
+            None => "(unset)".dim().to_string(),
+            Some(_) if !reveal && is_secret(&key) => {
+                "********".dim().to_string()
+            }
+            Some(value) => value.replace('\n', "\\n"),
+        };
+        println!("{}={}", key.bold(), value);
+    }
+
+    Ok(())
+}
+
+/**
+ * Write a single variable file atomically, keeping the mode of an existing
+ * file (new files holding secrets are only readable by the owner).
+ */
+fn write_var(file: &Path, key: &str, value: &str) -> Result<()> {
+    let mode = match fs::metadata(file) {
+        Ok(meta) => meta.permissions().mode() & 0o7777,
+        Err(_) if is_secret(key) => 0o600,
+        Err(_) => 0o644,
+    };
+
+    // newlines are stored as NUL bytes (chpst turns them back into newlines)
+    let content = format!("{}\n", value.replace('\n', "\0"));
+
+    utils::write_atomic(file, content.as_bytes(), mode)
+}
+
+/// Describe the current state of a variable (for the audit log, no values).
+fn describe(file: &Path) -> String {
+    match fs::metadata(file) {
+        Ok(meta) if meta.len() == 0 => "removed".to_string(),
+        Ok(_) => "set".to_string(),
+        Err(_) => "unset".to_string(),
+    }
+}
+
+/// Check if a variable name matches any of the secret patterns.
+pub fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SECRET_PATTERNS.iter().any(|pattern| glob_match(pattern, &key))
+}
+
+/// Match a string against a pattern where `*` matches any characters.
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().expect("split yields a part");
+    let Some(mut s) = s.strip_prefix(first) else {
+        return false;
+    };
+
+    let Some((last, middle)) = rest.split_last() else {
+        return s.is_empty();
+    };
+
+    for part in middle {
+        match s.find(part) {
+            Some(idx) => s = &s[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    s.ends_with(last)
+}
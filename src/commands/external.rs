@@ -0,0 +1,35 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: February 15, 2022
+ * License: MIT
+ */
+
+//! `vsv <any other command>` (passed directly to `sv`).
+
+use std::env;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use yansi::Paint;
+
+use crate::config;
+use crate::config::Config;
+use crate::utils::verbose;
+
+/// Handle an external subcommand by calling `SV_PROG` (`sv`) with it.
+pub fn do_external(cfg: &Config) -> Result<()> {
+    let sv = env::var(config::ENV_SV_PROG)
+        .unwrap_or_else(|_| config::DEFAULT_SV_PROG.to_string());
+
+    verbose!(cfg, "calling {} {:?}", sv, cfg.operands);
+
+    let status = Command::new(&sv)
+        .args(&cfg.operands)
+        .env(config::ENV_SVDIR, &cfg.svdir)
+        .status()
+        .with_context(|| format!("failed to execute {}", sv))?;
+
+    ensure!(status.success(), "{} exited with {}", sv, status);
+
+    Ok(())
+}
@@ -0,0 +1,397 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv lint` (check service directories for common mistakes).
+//!
+//! Most of these mistakes don't stop a service from being added, they just
+//! show up later as a service stuck in "n/a", flapping, or a stray shell being
+//! supervised instead of the daemon.
+
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Result};
+use yansi::Paint;
+
+use crate::config::Config;
+use crate::runit::get_services;
+use crate::utils;
+
+/// Scripts run by runsv, checked if they exist.
+const SCRIPTS: &[&str] = &["run", "finish", "check", "log/run", "log/finish"];
+
+/// Programs that count as a logger in `log/run`.
+const LOGGERS: &[&str] = &[
+    "svlogd", "vlogger", "logger", "socklog", "s6-log", "multilog", "tinylog",
+];
+
+/// Shell words that can end a script after the final command.
+const CLOSING_WORDS: &[&str] = &["fi", "done", "esac", "}", ";;"];
+
+/// Severity of a lint finding.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Level {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+
+        s.fmt(f)
+    }
+}
+
+/// A single problem found in a service directory.
+#[derive(Debug)]
+struct Finding {
+    level: Level,
+    file: String,
+    message: String,
+}
+
+/// All of the findings for a single service.
+#[derive(Debug)]
+struct Report {
+    name: String,
+    path: PathBuf,
+    findings: Vec<Finding>,
+}
+
+impl Report {
+    fn error(&mut self, file: &str, message: impl Into<String>) {
+        self.add(Level::Error, file, message.into());
+    }
+
+    fn warning(&mut self, file: &str, message: impl Into<String>) {
+        self.add(Level::Warning, file, message.into());
+    }
+
+    fn add(&mut self, level: Level, file: &str, message: String) {
+        self.findings.push(Finding { level, file: file.to_string(), message });
+    }
+
+    fn count(&self, level: Level) -> usize {
+        self.findings.iter().filter(|f| f.level == level).count()
+    }
+}
+
+/// Handle `vsv lint [svc...]`.
+pub fn do_lint(cfg: &Config, json: bool) -> Result<()> {
+    let names: Vec<String> = if cfg.operands.is_empty() {
+        let mut names: Vec<String> =
+            get_services(&cfg.svdir, false, None::<&str>, cfg.root.as_deref())?
+                .into_iter()
+                .map(|svc| svc.name)
+                .collect();
+        names.sort();
+        names
+    } else {
+        cfg.operands.clone()
+    };
+
+    let reports: Vec<Report> =
+        names.iter().map(|name| lint_service(cfg, name)).collect();
+
+    if json {
+        print_json(&reports);
+    } else {
+        print_text(&reports);
+    }
+
+    let errors: usize = reports.iter().map(|r| r.count(Level::Error)).sum();
+    ensure!(errors == 0, "{} error(s) found", errors);
+
+    Ok(())
+}
+
+/// Run every check against a single service.
+fn lint_service(cfg: &Config, name: &str) -> Report {
+    let link = cfg.svdir.join(name);
+    let mut report =
+        Report { name: name.to_string(), path: link.clone(), findings: vec![] };
+
+    if cfg.is_broken_link(&link) {
+        let target = fs::read_link(&link).unwrap_or_default();
+        report.error("", format!("broken symlink to {}", target.display()));
+        return report;
+    }
+
+    let dir = match cfg.find_definition(name) {
+        Ok(dir) => dir,
+        Err(err) => {
+            report.error("", err.to_string());
+            return report;
+        }
+    };
+    report.path = dir.clone();
+
+    // the definition lives in an avail dir (shared by every svdir it's added
+    // to) rather than directly in the svdir
+    let in_avail = cfg.avail_dirs.iter().any(|avail| {
+        cfg.canonicalize(avail).is_ok_and(|avail| dir.starts_with(avail))
+    });
+
+    let run = dir.join("run");
+    if !run.exists() {
+        report.error("run", "missing");
+    }
+
+    for file in SCRIPTS {
+        lint_script(cfg, &mut report, &dir, file);
+    }
+
+    if in_avail {
+        if dir.join("down").exists() {
+            report.warning(
+                "down",
+                "down file in the avail dir (applies to every runlevel the \
+                 service is added to)",
+            );
+        }
+
+        for supervise in ["supervise", "log/supervise"] {
+            let path = dir.join(supervise);
+            let is_dir = fs::symlink_metadata(&path)
+                .is_ok_and(|meta| meta.file_type().is_dir());
+            if is_dir {
+                report.warning(
+                    supervise,
+                    "runtime directory in the avail dir (should be a symlink \
+                     to /run/runit/supervise.<name>)",
+                );
+            }
+        }
+    }
+
+    let conf = dir.join("conf");
+    if let Ok(content) = fs::read(&conf) {
+        if content.windows(2).any(|w| w == b"\r\n") {
+            report.error("conf", "CRLF line endings");
+        }
+    }
+
+    report
+}
+
+/// Check a single script (if it exists).
+fn lint_script(cfg: &Config, report: &mut Report, dir: &Path, file: &str) {
+    let path = dir.join(file);
+    let Ok(meta) = fs::metadata(&path) else {
+        return;
+    };
+
+    if !meta.is_file() {
+        report.error(file, "not a regular file");
+        return;
+    }
+    if meta.permissions().mode() & 0o111 == 0 {
+        report.error(file, "not executable");
+    }
+
+    let Ok(content) = fs::read(&path) else {
+        report.error(file, "not readable");
+        return;
+    };
+
+    // a binary (e.g. log/run symlinked to vlogger) can't be checked further
+    if content.contains(&0) {
+        return;
+    }
+    let content = String::from_utf8_lossy(&content);
+
+    if content.contains("\r\n") {
+        report.error(file, "CRLF line endings");
+    }
+
+    let first_line = content.lines().next().unwrap_or("");
+    if !first_line.starts_with("#!") {
+        report.error(file, "missing shebang (#!/bin/sh)");
+        return;
+    }
+    if !first_line.contains("sh") {
+        return;
+    }
+
+    let lines = script_lines(&content);
+
+    if file == "run" || file == "log/run" {
+        if let Some(last) = lines.last() {
+            let closing = CLOSING_WORDS.iter().any(|w| last.starts_with(w));
+            if !closing && !last.starts_with("exec ") {
+                report.warning(
+                    file,
+                    format!(
+                        "last command doesn't use exec, the shell will be \
+                         supervised instead: {}",
+                        last
+                    ),
+                );
+            }
+        }
+    }
+
+    if file == "log/run" {
+        let has_logger = lines.iter().any(|line| {
+            line.split_whitespace().any(|word| {
+                let cmd = word.rsplit('/').next().unwrap_or(word);
+                LOGGERS.contains(&cmd)
+            })
+        });
+        if !has_logger {
+            report.warning(
+                file,
+                format!("doesn't run a logger ({})", LOGGERS.join(", ")),
+            );
+        }
+    }
+
+    for line in &lines {
+        for dep in sv_check_deps(line) {
+            if dep.contains('/') {
+                if !cfg.exists(&cfg.rooted(Path::new(&dep))) {
+                    report.error(file, format!("sv check on missing {}", dep));
+                }
+            } else if !cfg.exists(&cfg.svdir.join(&dep)) {
+                if cfg.find_avail(&dep).is_some() {
+                    report.warning(
+                        file,
+                        format!("sv check on {} which isn't added", dep),
+                    );
+                } else {
+                    report.error(
+                        file,
+                        format!("sv check on {} which doesn't exist", dep),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Get the lines of a script with comments and blank lines removed.
+fn script_lines(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/**
+ * Find the services named in `sv check` (like `sv check dbus >/dev/null ||
+ * exit 1`) in a line of shell.
+ */
+fn sv_check_deps(line: &str) -> Vec<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut deps = vec![];
+
+    for (i, word) in words.iter().enumerate() {
+        if word.rsplit('/').next() != Some("sv") {
+            continue;
+        }
+
+        // skip options like `-w 5` or `-v`
+        let mut rest = words[i + 1..].iter();
+        let mut command = None;
+        while let Some(word) = rest.next() {
+            if *word == "-w" {
+                rest.next();
+            } else if !word.starts_with('-') {
+                command = Some(*word);
+                break;
+            }
+        }
+        if command != Some("check") {
+            continue;
+        }
+
+        for word in rest {
+            if word.contains(['>', '<', '$'])
+                || word.starts_with(['|', '&', ';'])
+            {
+                break;
+            }
+            deps.push(word.trim_end_matches(';').to_string());
+            if word.ends_with(';') {
+                break;
+            }
+        }
+    }
+
+    deps
+}
+
+/// Print the findings for people.
+fn print_text(reports: &[Report]) {
+    for report in reports {
+        if report.findings.is_empty() {
+            println!("{}: {}", report.name.bold(), "ok".green());
+            continue;
+        }
+
+        for finding in &report.findings {
+            let level = match finding.level {
+                Level::Error => finding.level.red(),
+                Level::Warning => finding.level.yellow(),
+            };
+            let location = if finding.file.is_empty() {
+                report.name.clone()
+            } else {
+                format!("{}/{}", report.name, finding.file)
+            };
+            println!("{}: {}: {}", location.bold(), level, finding.message);
+        }
+    }
+
+    let errors: usize = reports.iter().map(|r| r.count(Level::Error)).sum();
+    let warnings: usize = reports.iter().map(|r| r.count(Level::Warning)).sum();
+    println!();
+    println!(
+        "{} service(s) checked, {} error(s), {} warning(s)",
+        reports.len(),
+        errors,
+        warnings
+    );
+}
+
+/// Print the findings as JSON.
+fn print_json(reports: &[Report]) {
+    let reports: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let findings: Vec<String> = report
+                .findings
+                .iter()
+                .map(|f| {
+                    format!(
+                        "{{\"level\":{},\"file\":{},\"message\":{}}}",
+                        utils::json_string(&f.level.to_string()),
+                        utils::json_string(&f.file),
+                        utils::json_string(&f.message)
+                    )
+                })
+                .collect();
+
+            format!(
+                "{{\"service\":{},\"path\":{},\"errors\":{},\"warnings\":{},\"findings\":[{}]}}",
+                utils::json_string(&report.name),
+                utils::json_string(&report.path.display().to_string()),
+                report.count(Level::Error),
+                report.count(Level::Warning),
+                findings.join(",")
+            )
+        })
+        .collect();
+
+    println!("[{}]", reports.join(","));
+}
@@ -6,7 +6,23 @@
 
 //! Subcommands for `vsv`.
 
+pub mod action;
 pub mod add_remove;
+pub mod audit;
+pub mod cat;
+pub mod check;
+pub mod clone;
+pub mod conf;
 pub mod control;
+pub mod diff;
+pub mod edit;
 pub mod enable_disable;
+pub mod env;
+pub mod external;
+pub mod lint;
+pub mod new;
+pub mod prune;
+pub mod runlevel;
 pub mod status;
+pub mod sv;
+pub mod undo;
@@ -0,0 +1,217 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv new` (scaffold a new service directory).
+//!
+//! Every generated file comes from a template, which can be overridden by
+//! placing a file with the same name in `<config dir>/templates/`.  The
+//! templates can use `{{name}}`, `{{command}}`, `{{user}}`, `{{finish}}`, and
+//! `{{check}}`.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use yansi::Paint;
+
+use crate::arguments::Logger;
+use crate::audit;
+use crate::commands::add_remove;
+use crate::config;
+use crate::config::Config;
+use crate::utils;
+
+const TEMPLATE_RUN: &str = "\
+#!/bin/sh
+exec 2>&1
+exec {{command}}
+";
+
+const TEMPLATE_FINISH: &str = "\
+#!/bin/sh
+{{finish}}
+";
+
+const TEMPLATE_CHECK: &str = "\
+#!/bin/sh
+exec {{check}}
+";
+
+const TEMPLATE_LOG_VLOGGER: &str = "\
+#!/bin/sh
+exec vlogger -t {{name}} -p daemon
+";
+
+const TEMPLATE_LOG_SVLOGD: &str = "\
+#!/bin/sh
+[ -d /var/log/{{name}} ] || mkdir -p /var/log/{{name}}
+exec svlogd -tt /var/log/{{name}}
+";
+
+/// Options for `vsv new`.
+#[derive(Debug)]
+pub struct NewOptions<'a> {
+    pub user: Option<&'a str>,
+    pub finish: Option<&'a str>,
+    pub check: Option<&'a str>,
+    pub log: Option<Logger>,
+    pub add: bool,
+}
+
+/// Handle `vsv new <name> -- <command...>`.
+pub fn do_new(
+    cfg: &Config,
+    name: &str,
+    command: &[String],
+    opts: &NewOptions,
+) -> Result<()> {
+    ensure!(
+        !name.is_empty()
+            && !name.contains('/')
+            && !name.starts_with('.')
+            && name != "log",
+        "invalid service name: {:?}",
+        name
+    );
+    ensure!(!command.is_empty(), "a command is required");
+
+    let avail_dir = cfg
+        .avail_dirs
+        .first()
+        .context("no avail dir to create the service in")?;
+    let dir = avail_dir.join(name);
+    if fs::symlink_metadata(&dir).is_ok() {
+        bail!("{} already exists", dir.display());
+    }
+
+    let mut command = shell_join(command);
+    if let Some(user) = opts.user {
+        command = format!("chpst -u {} {}", shell_quote(user), command);
+    }
+
+    let vars = [
+        ("name", name.to_string()),
+        ("command", command),
+        ("user", opts.user.unwrap_or_default().to_string()),
+        ("finish", opts.finish.unwrap_or_default().to_string()),
+        ("check", opts.check.unwrap_or_default().to_string()),
+    ];
+
+    // (template name, path relative to the service dir, default template)
+    let mut files = vec![("run", "run", TEMPLATE_RUN)];
+    if opts.finish.is_some() {
+        files.push(("finish", "finish", TEMPLATE_FINISH));
+    }
+    if opts.check.is_some() {
+        files.push(("check", "check", TEMPLATE_CHECK));
+    }
+    match opts.log {
+        Some(Logger::Vlogger) => {
+            files.push(("log-vlogger", "log/run", TEMPLATE_LOG_VLOGGER));
+        }
+        Some(Logger::Svlogd) => {
+            files.push(("log-svlogd", "log/run", TEMPLATE_LOG_SVLOGD));
+        }
+        None => {}
+    }
+
+    print!("{} service {}... ", "creating".bold(), name.bold());
+
+    // build the service in a temp dir so a half-written service never shows
+    // up in the avail dir
+    let tmp_dir = avail_dir.join(format!(".{}.vsv-new", name));
+    let ret = fs::create_dir_all(avail_dir)
+        .map_err(Into::into)
+        .and_then(|()| write_service(cfg, &tmp_dir, &files, &vars))
+        .and_then(|()| fs::rename(&tmp_dir, &dir).map_err(Into::into));
+    audit::record(cfg, "new", name, "-", &ret);
+
+    if let Err(err) = ret {
+        println!("{}", "failed".red());
+        let _ = fs::remove_dir_all(&tmp_dir);
+        return Err(err).with_context(|| format!("failed to create {:?}", dir));
+    }
+
+    println!("{}", "done".green());
+    for (_, file, _) in &files {
+        println!("  {}", dir.join(file).display());
+    }
+
+    let add = opts.add
+        || utils::confirm(&format!("add service {} now?", name.bold()))?;
+    if add {
+        add_remove::do_add(cfg, &[name.to_string()], false, false)?;
+    }
+
+    Ok(())
+}
+
+/// Write all of the service files (from templates) into `dir`.
+fn write_service(
+    cfg: &Config,
+    dir: &Path,
+    files: &[(&str, &str, &str)],
+    vars: &[(&str, String)],
+) -> Result<()> {
+    let templates_dir = cfg.config_dir.join(config::TEMPLATES_DIR_NAME);
+
+    fs::create_dir(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o755))?;
+
+    for (template, file, default) in files {
+        let template_file = templates_dir.join(template);
+        let template = match fs::read_to_string(&template_file) {
+            Ok(s) => s,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                default.to_string()
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("failed to read template {:?}", template_file)
+                });
+            }
+        };
+
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, render(&template, vars))?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+/// Replace every `{{key}}` in the template.
+fn render(template: &str, vars: &[(&str, String)]) -> String {
+    let mut s = template.to_string();
+    for (key, value) in vars {
+        s = s.replace(&format!("{{{{{}}}}}", key), value);
+    }
+    s
+}
+
+/// Join arguments into a shell command line, quoting where needed.
+fn shell_join(args: &[String]) -> String {
+    let args: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+    args.join(" ")
+}
+
+/// Quote a single shell word (if needed).
+fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
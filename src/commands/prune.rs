@@ -0,0 +1,80 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv prune` (remove broken service symlinks).
+
+use std::fs;
+
+use anyhow::{ensure, Context, Result};
+use yansi::Paint;
+
+use crate::audit;
+use crate::config::Config;
+use crate::journal::Recorder;
+use crate::runit;
+use crate::utils;
+
+/// Handle `vsv prune`.
+pub fn do_prune(cfg: &Config, yes: bool, dry_run: bool) -> Result<()> {
+    let broken: Vec<_> = runit::get_services(
+        &cfg.svdir,
+        false,
+        None::<&str>,
+        cfg.root.as_deref(),
+    )
+    .with_context(|| format!("failed to list services in {:?}", cfg.svdir))?
+    .into_iter()
+    .filter(|svc| cfg.is_broken_link(&svc.path))
+    .collect();
+
+    if broken.is_empty() {
+        println!("no broken services in {:?}", cfg.svdir);
+        return Ok(());
+    }
+
+    println!("{}", format!("Broken services in {:?}:", cfg.svdir).bold());
+    for svc in &broken {
+        let target = fs::read_link(&svc.path)
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        println!("{} -> {}", svc.name, target.red());
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    if !yes {
+        let prompt = format!("remove {} broken service(s)?", broken.len());
+        if !utils::confirm(&prompt)? {
+            println!("aborted");
+            return Ok(());
+        }
+    }
+
+    let mut had_error = false;
+    let recorder = Recorder::begin(cfg, "prune");
+
+    for svc in &broken {
+        print!("{} service {}... ", "removing".bold(), svc.name.bold());
+
+        recorder.snapshot_link(svc);
+        let ret = fs::remove_file(&svc.path);
+        audit::record(cfg, "prune", &svc.name, "broken", &ret);
+
+        match ret {
+            Ok(()) => println!("{}", "done".green()),
+            Err(err) => {
+                println!("{}", format!("failed! {}", err).red());
+                had_error = true;
+            }
+        }
+    }
+
+    ensure!(!had_error, "failed to remove broken service(s)");
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv runlevel` (list, switch, and compare runlevels).
+//!
+//! Void keeps each runlevel as a directory of service symlinks in
+//! `/etc/runit/runsvdir`, with the `current` symlink pointing at the active
+//! one (and `/var/service` pointing at `current`).
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use yansi::Paint;
+
+use crate::arguments::RunlevelAction;
+use crate::audit;
+use crate::config::Config;
+use crate::runit;
+
+/// Handle `vsv runlevel [switch <name> | matrix]`.
+pub fn do_runlevel(
+    cfg: &Config,
+    action: Option<&RunlevelAction>,
+) -> Result<()> {
+    match action {
+        None => list_runlevels(cfg),
+        Some(RunlevelAction::Switch { runlevel }) => {
+            switch_runlevel(cfg, runlevel)
+        }
+        Some(RunlevelAction::Matrix) => print_matrix(cfg),
+    }
+}
+
+/// Print every runlevel with the number of services in it.
+fn list_runlevels(cfg: &Config) -> Result<()> {
+    let runlevels = runit::get_runlevels(&cfg.runlevel_dir)?;
+    let current = runit::current_runlevel(&cfg.runlevel_dir);
+
+    println!(
+        "{}",
+        format!("Runlevels in {}:", cfg.runlevel_dir.display()).bold()
+    );
+
+    let width = runlevels.iter().map(String::len).max().unwrap_or(0).max(20);
+    println!("{: <width$} SERVICES", "RUNLEVEL", width = width);
+
+    for name in &runlevels {
+        let count = runit::get_services(
+            &cfg.runlevel_dir.join(name),
+            false,
+            None::<&str>,
+            cfg.root.as_deref(),
+        )
+        .map(|services| services.len().to_string())
+        .unwrap_or_else(|_| "?".to_string());
+
+        if current.as_ref() == Some(name) {
+            println!(
+                "{} {: <8} {}",
+                format!("{: <width$}", name, width = width).green(),
+                count,
+                "(current)".green()
+            );
+        } else {
+            println!("{: <width$} {}", name, count, width = width);
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Switch the current runlevel the way runsvchdir(8) does: `current.new` is
+ * created pointing at the new runlevel, the old `current` becomes `previous`,
+ * and `current.new` is renamed over `current` (runsvdir picks up the change on
+ * its next scan).
+ */
+fn switch_runlevel(cfg: &Config, name: &str) -> Result<()> {
+    let dir = &cfg.runlevel_dir;
+    runit::runlevel_path(dir, name)?;
+
+    let old = runit::current_runlevel(dir);
+    if old.as_deref() == Some(name) {
+        println!("already in runlevel {}", name.bold());
+        return Ok(());
+    }
+
+    print!("{} to runlevel {}... ", "switching".bold(), name.bold());
+
+    let ret = swap_current(dir, name);
+    audit::record(
+        cfg,
+        "runlevel switch",
+        name,
+        old.as_deref().unwrap_or("-"),
+        &ret,
+    );
+    if let Err(err) = ret {
+        println!("{}", "failed!".red());
+        return Err(err);
+    }
+
+    println!("{}", "done".green());
+
+    Ok(())
+}
+
+/// Point `current` at the runlevel, keeping the old one as `previous`.
+fn swap_current(dir: &Path, name: &str) -> Result<()> {
+    let current = dir.join("current");
+    let previous = dir.join("previous");
+    let new = dir.join("current.new");
+
+    if let Ok(meta) = fs::symlink_metadata(&current) {
+        if !meta.file_type().is_symlink() {
+            bail!("{:?} is not a symlink", current);
+        }
+    }
+
+    // a leftover from an interrupted switch
+    remove_if_exists(&new)?;
+    symlink(name, &new)
+        .with_context(|| format!("failed to create symlink {:?}", new))?;
+
+    if fs::symlink_metadata(&current).is_ok() {
+        remove_if_exists(&previous)?;
+        fs::rename(&current, &previous).with_context(|| {
+            format!("failed to rename {:?} to {:?}", current, previous)
+        })?;
+    }
+
+    if let Err(err) = fs::rename(&new, &current) {
+        // put the old runlevel back
+        let _ = fs::rename(&previous, &current);
+        return Err(err).with_context(|| format!("failed to rename {:?}", new));
+    }
+
+    Ok(())
+}
+
+/// Remove a file, ignoring it if it doesn't exist.
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("failed to remove {:?}", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Print a table of which services are in which runlevels.
+fn print_matrix(cfg: &Config) -> Result<()> {
+    let runlevels = runit::get_runlevels(&cfg.runlevel_dir)?;
+    let current = runit::current_runlevel(&cfg.runlevel_dir);
+
+    let mut columns = vec![];
+    let mut names: Vec<String> = vec![];
+    for runlevel in &runlevels {
+        let services = runit::get_services(
+            &cfg.runlevel_dir.join(runlevel),
+            false,
+            None::<&str>,
+            cfg.root.as_deref(),
+        )?;
+        let services: Vec<String> =
+            services.into_iter().map(|svc| svc.name).collect();
+        names.extend(services.iter().cloned());
+        columns.push(services);
+    }
+    names.sort();
+    names.dedup();
+
+    let name_width = names.iter().map(String::len).max().unwrap_or(0).max(20);
+
+    print!("{: <width$}", "SERVICE", width = name_width);
+    for runlevel in &runlevels {
+        let header = format!(" {}", runlevel);
+        if current.as_ref() == Some(runlevel) {
+            print!("{}", header.green());
+        } else {
+            print!("{}", header);
+        }
+    }
+    println!();
+
+    for name in &names {
+        print!("{: <width$}", name, width = name_width);
+        for (i, (runlevel, services)) in
+            runlevels.iter().zip(&columns).enumerate()
+        {
+            // pad every column but the last one
+            let width =
+                if i + 1 < runlevels.len() { runlevel.len() } else { 0 };
+            if services.contains(name) {
+                print!(" {}", format!("{: <width$}", "✔").green());
+            } else {
+                print!(" {}", format!("{: <width$}", "·").dim());
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
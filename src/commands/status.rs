@@ -6,13 +6,17 @@
 
 //! `vsv status` subcommand.
 
+use std::path::Path;
+
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use yansi::{Paint, Style};
 
 use crate::config::Config;
-use crate::runit;
+use crate::health::{self, Health, HealthCache};
+use crate::runit::{self, RunitService};
 use crate::service::Service;
+use crate::xbps::PkgDb;
 use crate::{utils, utils::verbose};
 
 /// Handle `vsv status` or `vsv` without a subcommand given.
@@ -20,29 +24,147 @@ pub fn do_status(cfg: &Config) -> Result<()> {
     // may or may not be set (option)
     let filter = cfg.operands.first();
 
-    // find all services
-    let services = runit::get_services(&cfg.svdir, cfg.log, filter)
-        .with_context(|| {
-            format!("failed to list services in {:?}", cfg.svdir)
-        })?;
+    // the svdirs to look in, with their scope name (`--all-scopes`)
+    let dirs: Vec<(Option<&str>, &Path)> = if cfg.all_scopes {
+        cfg.scopes
+            .iter()
+            .map(|scope| (Some(scope.name.as_str()), scope.svdir.as_path()))
+            .collect()
+    } else {
+        vec![(None, cfg.svdir.as_path())]
+    };
+
+    // find all services (in every svdir in parallel), along with their scope
+    // and the name of the top level service (for log services)
+    let found: Vec<Vec<(Option<&str>, String, RunitService)>> = dirs
+        .par_iter()
+        .map(|(scope, dir)| {
+            let services = match runit::get_services(
+                dir,
+                cfg.log,
+                filter,
+                cfg.root.as_deref(),
+            ) {
+                Ok(services) => services,
+                // a user's svdir shouldn't hide every other scope
+                Err(err) if scope.is_some() => {
+                    verbose!(cfg, "skipping {:?}: {:?}", dir, err);
+                    return Ok(vec![]);
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("failed to list services in {:?}", dir)
+                    });
+                }
+            };
+
+            let services = services
+                .into_iter()
+                .map(|svc| {
+                    let top_name = svc
+                        .path
+                        .strip_prefix(dir)
+                        .ok()
+                        .and_then(|p| p.iter().next())
+                        .and_then(|s| s.to_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    // broken links are kept as is to show them as broken
+                    let path = if cfg.is_broken_link(&svc.path) {
+                        svc.path.clone()
+                    } else {
+                        cfg.resolve_service(&svc.path)
+                    };
+                    (*scope, top_name, RunitService::new(&svc.name, &path))
+                })
+                .collect();
+
+            Ok(services)
+        })
+        .collect::<Result<_>>()?;
+    let found: Vec<_> = found.into_iter().flatten().collect();
+
+    // health checks are cached so they don't all run every time
+    let cache = cfg
+        .health
+        .then(|| HealthCache::load(&cfg.health_cache, cfg.health_ttl));
+
+    // which package installed each service (always included in JSON)
+    let pkgdb = (cfg.package || cfg.json)
+        .then(|| PkgDb::load(&cfg.xbps_dbdir, cfg.root.as_deref()));
 
     // loop each service found (just gather data here, can be done in parallel)
-    let services: Vec<(Service, Vec<String>)> = services
+    let services: Vec<(Service, Vec<String>, Option<Health>)> = found
         .par_iter()
-        .map(|service| {
-            Service::from_runit_service(
+        .map(|(scope, _, service)| {
+            let broken = cfg.is_broken_link(&service.path);
+            let (mut svc, messages) = Service::from_runit_service(
                 service,
+                broken,
                 cfg.tree,
                 !cfg.log, // If we are logging (-l), do NOT show the log status column
                 &cfg.proc_path,
-            )
+            );
+
+            if let Some(scope) = scope {
+                svc.set_scope(scope);
+            }
+
+            // only fresh results need to be written back to the cache
+            let mut fresh = None;
+            if let Some(cache) = &cache {
+                let health = if broken {
+                    Health::NoCheck
+                } else {
+                    cache.get(service).unwrap_or_else(|| {
+                        let health =
+                            health::run_check(service, cfg.check_timeout);
+                        fresh = Some(health.clone());
+                        health
+                    })
+                };
+                svc.set_health(health);
+            }
+
+            if let Some(pkgdb) = &pkgdb {
+                let package = cfg
+                    .canonicalize(&service.path)
+                    .ok()
+                    .and_then(|dir| pkgdb.service_owner(&dir).cloned());
+                svc.set_package(package);
+            }
+
+            (svc, messages, fresh)
         })
         .collect();
 
+    if let Some(mut cache) = cache {
+        let mut dirty = false;
+        for ((_, _, service), (_, _, fresh)) in found.iter().zip(&services) {
+            if let Some(health) = fresh {
+                cache.insert(service, health);
+                dirty = true;
+            }
+        }
+
+        if dirty {
+            if let Err(err) = cache.save() {
+                verbose!(cfg, "failed to save health cache: {}", err);
+            }
+        }
+    }
+
+    if cfg.json {
+        let services: Vec<String> =
+            services.iter().map(|(svc, _, _)| svc.to_json()).collect();
+        println!("[{}]", services.join(","));
+        return Ok(());
+    }
+
     // print gathared data
     let style = Style::default();
 
-    verbose!(cfg, "found {} services in {:?}", services.len(), cfg.svdir);
+    verbose!(cfg, "found {} services in {:?}", services.len(), dirs);
     println!();
 
     // Hide the "LOG" header if we are in log mode
@@ -52,6 +174,7 @@ pub fn do_status(cfg: &Config) -> Result<()> {
         "{}",
         utils::format_status_line(
             ("", style.bold()),
+            cfg.all_scopes.then(|| ("SCOPE", style.bold())),
             ("SERVICE", style.bold()),
             ("STATE", style.bold()),
             ("ENABLED", style.bold()),
@@ -59,11 +182,47 @@ pub fn do_status(cfg: &Config) -> Result<()> {
             ("COMMAND", style.bold()),
             ("TIME", style.bold()),
             (log_header, style.bold()),
+            cfg.health.then(|| ("HEALTH", style.bold())),
+            cfg.package.then(|| ("PACKAGE", style.bold())),
         )
     );
 
-    // print each service found
-    for (service, messages) in services {
+    // print each service found, with template instances (`name@instance`)
+    // grouped under a line for their template
+    let mut group: Option<(Option<&str>, &str)> = None;
+    for ((scope, top_name, runit_service), (mut service, messages, _)) in
+        found.iter().zip(services)
+    {
+        // log services belong to the same group as their service
+        match runit::split_instance(top_name) {
+            Some((base, instance)) => {
+                if group != Some((*scope, base)) {
+                    let template = format!("{}@", base);
+                    println!(
+                        "{}",
+                        utils::format_status_line(
+                            ("", style),
+                            scope.map(|scope| (scope, style)),
+                            (template.as_str(), style.bold()),
+                            ("", style),
+                            ("", style),
+                            ("", style),
+                            ("", style),
+                            ("", style),
+                            ("", style),
+                            cfg.health.then_some(("", style)),
+                            cfg.package.then_some(("", style)),
+                        )
+                    );
+                    group = Some((*scope, base));
+                }
+                if runit_service.name == *top_name {
+                    service.set_display_name(&format!("@{}", instance));
+                }
+            }
+            None => group = None,
+        }
+
         println!("{}", service);
 
         // print pstree if applicable
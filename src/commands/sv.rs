@@ -0,0 +1,638 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `sv` compatible invocation mode (`vsv sv ...` or `vsv` called as `sv`).
+//!
+//! This mirrors the behavior of runit's `sv(8)` - command names (including
+//! abbreviations and LSB actions), the `-v` and `-w` options, output, and exit
+//! codes are all the same.
+
+use std::env;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::audit;
+use crate::config;
+use crate::config::Config;
+use crate::journal::Recorder;
+use crate::runit::{
+    RunitCommand, RunitService, RunitServiceState, RunitStatus,
+};
+
+/// Time to sleep between checks when waiting.
+const CHECK_INTERVAL: Duration = Duration::from_millis(420);
+
+/// Exit code used for usage errors.
+const EXIT_USAGE: i32 = 100;
+
+/// Maximum exit code for failed services (so it never looks like a usage
+/// error).
+const EXIT_MAX: i32 = 99;
+
+/// What to do for each service before (optionally) waiting.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Control,
+    Status,
+    Nothing,
+}
+
+/// State for a single invocation of `sv`.
+struct Sv<'a> {
+    cfg: &'a Config,
+    recorder: Recorder<'a>,
+    command: String,
+    action: Action,
+    acts: String,
+    check: bool,
+    kill: bool,
+    wait: Duration,
+    start: SystemTime,
+    rc: i32,
+}
+
+/// Handle `vsv sv ...`, returning the exit code to use.
+pub fn do_sv(cfg: &Config, args: &[String]) -> i32 {
+    let mut verbose = false;
+    let mut wait_secs = env::var(config::ENV_SVWAIT)
+        .map(|s| scan_ulong(&s))
+        .unwrap_or(config::DEFAULT_WAIT_SECS);
+
+    // getopt("w:vV")
+    let mut args = args.iter();
+    let mut operands: Vec<&String> = vec![];
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if !arg.starts_with('-') || arg.len() == 1 {
+            operands.push(arg);
+            break;
+        }
+
+        let mut chars = arg[1..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                'v' => verbose = true,
+                'w' => {
+                    let rest: String = chars.by_ref().collect();
+                    let value = if rest.is_empty() {
+                        match args.next() {
+                            Some(value) => value.to_string(),
+                            None => return usage(),
+                        }
+                    } else {
+                        rest
+                    };
+                    wait_secs = scan_ulong(&value);
+                }
+                'V' => {
+                    eprintln!("vsv {}", env!("CARGO_PKG_VERSION"));
+                    return usage();
+                }
+                _ => return usage(),
+            }
+        }
+    }
+    operands.extend(args);
+
+    let mut operands = operands.into_iter();
+    let Some(command) = operands.next() else {
+        return usage();
+    };
+    let services: Vec<&String> = operands.collect();
+    if services.is_empty() {
+        return usage();
+    }
+
+    let Some((action, acts, check, kill)) = parse_command(command, verbose)
+    else {
+        return usage();
+    };
+
+    let mut sv = Sv {
+        cfg,
+        recorder: Recorder::begin(cfg, &format!("sv {}", command)),
+        command: format!("sv {}", command),
+        action,
+        acts: acts.to_string(),
+        check,
+        kill,
+        wait: Duration::from_secs(wait_secs),
+        start: SystemTime::now(),
+        rc: 0,
+    };
+
+    sv.run(&services)
+}
+
+/**
+ * Parse an `sv` command name into an action, the control characters to send,
+ * and whether to wait for (check) and kill the service on timeout.
+ *
+ * Like `sv`, only the first character matters for most commands.
+ */
+fn parse_command(
+    command: &str,
+    verbose: bool,
+) -> Option<(Action, &'static str, bool, bool)> {
+    let first = command.chars().next()?;
+
+    let parsed = match first {
+        'x' | 'e' => (Action::Control, "x", verbose, false),
+        'X' | 'E' => (Action::Control, "x", true, true),
+        'D' => (Action::Control, "d", true, true),
+        'T' => (Action::Control, "tc", true, true),
+        'c' if command == "check" => (Action::Nothing, "C", true, false),
+        't' if command == "try-restart" => (Action::Control, "tc", true, false),
+        'u' | 'd' | 'o' | 't' | 'p' | 'h' | 'a' | 'i' | 'k' | 'q' | '1'
+        | '2' | 'c' => {
+            let acts = match first {
+                'u' => "u",
+                'd' => "d",
+                'o' => "o",
+                't' => "t",
+                'p' => "p",
+                'h' => "h",
+                'a' => "a",
+                'i' => "i",
+                'k' => "k",
+                'q' => "q",
+                '1' => "1",
+                '2' => "2",
+                _ => "c",
+            };
+            (Action::Control, acts, verbose, false)
+        }
+        's' => match command {
+            "shutdown" => (Action::Control, "x", true, false),
+            "start" => (Action::Control, "u", true, false),
+            "stop" => (Action::Control, "d", true, false),
+            _ => (Action::Status, "s", false, false),
+        },
+        'r' => match command {
+            "restart" => (Action::Control, "tcu", true, false),
+            "reload" => (Action::Control, "h", true, false),
+            _ => return None,
+        },
+        'f' => match command {
+            "force-reload" => (Action::Control, "tc", true, true),
+            "force-restart" => (Action::Control, "tcu", true, true),
+            "force-shutdown" => (Action::Control, "x", true, true),
+            "force-stop" => (Action::Control, "d", true, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(parsed)
+}
+
+impl Sv<'_> {
+    /// Run the command on all services, returning the exit code.
+    fn run(&mut self, names: &[&String]) -> i32 {
+        let mut services: Vec<Option<RunitService>> = vec![];
+
+        for name in names {
+            let svc = match self.service_path(name) {
+                Ok(path) => RunitService::new(name, &path),
+                Err(err) => {
+                    self.fail(name, &err.to_string(), None);
+                    services.push(None);
+                    continue;
+                }
+            };
+
+            let chdir = match fs::metadata(&svc.path) {
+                Ok(meta) if meta.is_dir() => Ok(()),
+                Ok(_) => Err(io::Error::from_raw_os_error(libc::ENOTDIR)),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = chdir {
+                self.fail(
+                    name,
+                    "unable to change to service directory",
+                    Some(&err),
+                );
+                services.push(None);
+                continue;
+            }
+
+            let ok = match self.action {
+                Action::Control => self.control(&svc, &self.acts.clone()) >= 0,
+                Action::Status => {
+                    self.status(&svc);
+                    true
+                }
+                Action::Nothing => true,
+            };
+
+            services.push(ok.then_some(svc));
+        }
+
+        if self.check {
+            let started = Instant::now();
+
+            loop {
+                let elapsed = started.elapsed();
+                let mut want_exit = true;
+
+                for slot in services.iter_mut() {
+                    let Some(svc) = slot else {
+                        continue;
+                    };
+
+                    if self.check_done(svc) != 0 {
+                        *slot = None;
+                        continue;
+                    }
+                    want_exit = false;
+
+                    if elapsed > self.wait {
+                        print!(
+                            "{}",
+                            if self.kill { "kill: " } else { "timeout: " }
+                        );
+                        if let Ok(Some(status)) = self.status_get(svc) {
+                            print!(
+                                "{}",
+                                status_line(&svc.name, &svc.path, &status)
+                            );
+                            self.rc += 1;
+                        }
+                        println!();
+
+                        if self.kill {
+                            self.control(svc, "k");
+                        }
+                        *slot = None;
+                    }
+                }
+
+                if want_exit {
+                    break;
+                }
+
+                thread::sleep(CHECK_INTERVAL);
+            }
+        }
+
+        self.rc.min(EXIT_MAX)
+    }
+
+    /// Resolve a service name to a directory (relative to `SVDIR`).
+    fn service_path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        if name.starts_with('/') || name.starts_with('.') {
+            Ok(PathBuf::from(name))
+        } else {
+            self.cfg.service_path(name)
+        }
+    }
+
+    /// Print a failure for a service (with the error if any).
+    fn fail(&mut self, name: &str, msg: &str, err: Option<&io::Error>) {
+        self.rc += 1;
+        match err {
+            Some(err) => {
+                println!("fail: {}: {}: {}", name, msg, error_str(err))
+            }
+            None => println!("fail: {}: {}", name, msg),
+        }
+    }
+
+    /// Print a warning for a service (with the error if any).
+    fn warn(&mut self, name: &str, msg: &str, err: Option<&io::Error>) {
+        self.rc += 1;
+        match err {
+            Some(err) => {
+                println!("warning: {}: {}: {}", name, msg, error_str(err))
+            }
+            None => println!("warning: {}: {}", name, msg),
+        }
+    }
+
+    /**
+     * Get the status of a service.
+     *
+     * Returns `Err(())` (after printing why) if the status can't be read, and
+     * `Ok(None)` if runsv isn't running.
+     */
+    fn status_get(
+        &mut self,
+        svc: &RunitService,
+    ) -> Result<Option<RunitStatus>, ()> {
+        let ok = svc.path.join("supervise").join("ok");
+        if let Err(err) = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(ok)
+        {
+            if err.raw_os_error() == Some(libc::ENXIO) {
+                self.not_running(svc);
+                return Ok(None);
+            }
+            self.warn(&svc.name, "unable to open supervise/ok", Some(&err));
+            return Err(());
+        }
+
+        match svc.get_status() {
+            Ok(status) => Ok(Some(status)),
+            Err(err) => {
+                match err.downcast_ref::<io::Error>() {
+                    Some(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                        self.warn(
+                            &svc.name,
+                            "unable to read supervise/status: bad format",
+                            None,
+                        );
+                    }
+                    err => self.warn(
+                        &svc.name,
+                        "unable to open supervise/status",
+                        err,
+                    ),
+                }
+                Err(())
+            }
+        }
+    }
+
+    /// Report that runsv isn't running (which is fine when exiting).
+    fn not_running(&mut self, svc: &RunitService) {
+        if self.acts.starts_with('x') {
+            println!("ok: {}: runsv not running", svc.name);
+        } else {
+            self.fail(&svc.name, "runsv not running", None);
+        }
+    }
+
+    /// Handle `sv status`.
+    fn status(&mut self, svc: &RunitService) {
+        let Ok(Some(status)) = self.status_get(svc) else {
+            return;
+        };
+
+        print!("{}", status_line(&svc.name, &svc.path, &status));
+
+        let log = svc.path.join("log");
+        match fs::metadata(&log) {
+            Ok(meta) if meta.is_dir() => {
+                let log_svc = RunitService::new(&svc.name, &log);
+                if let Ok(Some(status)) = self.status_get(&log_svc) {
+                    print!("; {}", status_line("log", &log, &status));
+                }
+            }
+            Ok(_) => print!(
+                "; log: warning: unable to change to log service \
+                 directory: {}",
+                error_str(&io::Error::from_raw_os_error(libc::ENOTDIR))
+            ),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => print!(
+                "; log: warning: unable to change to log service \
+                 directory: {}",
+                error_str(&err)
+            ),
+        }
+
+        println!();
+    }
+
+    /**
+     * Send control characters to a service.
+     *
+     * Returns -1 on failure, 0 if nothing had to be done, and 1 otherwise.
+     */
+    fn control(&mut self, svc: &RunitService, acts: &str) -> i32 {
+        let Ok(Some(status)) = self.status_get(svc) else {
+            return -1;
+        };
+
+        let first = acts.chars().next().unwrap_or(' ');
+        if status.want == first && (first != 'd' || status.got_term) {
+            return 0;
+        }
+
+        if acts.contains(['u', 'd', 'o']) {
+            self.recorder.snapshot_want(svc);
+        }
+
+        let cmds: Vec<RunitCommand> =
+            acts.chars().filter_map(RunitCommand::from_char).collect();
+        let ret = svc.control_all(&cmds);
+
+        audit::record(
+            self.cfg,
+            &self.command,
+            &svc.name,
+            &status.state.to_string(),
+            &ret,
+        );
+
+        if let Err(err) = ret {
+            let io_err =
+                err.chain().find_map(|e| e.downcast_ref::<io::Error>());
+
+            match io_err {
+                Some(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                    self.not_running(svc);
+                }
+                e => {
+                    self.warn(&svc.name, "unable to open supervise/control", e)
+                }
+            }
+            return -1;
+        }
+
+        1
+    }
+
+    /**
+     * Check if a command has taken effect on a service.
+     *
+     * Returns -1 on failure, 0 if not yet done, and 1 (after printing the
+     * status) once done.
+     */
+    fn check_done(&mut self, svc: &RunitService) -> i32 {
+        let status = match self.status_get(svc) {
+            Ok(Some(status)) => status,
+            Ok(None) if self.acts.starts_with('x') => return 1,
+            _ => return -1,
+        };
+
+        let pid = status.pid.is_some();
+        let changed_before_start = status
+            .start_time
+            .is_none_or(|t| unix_secs(t) < unix_secs(self.start));
+
+        let acts = self.acts.clone();
+        for c in acts.chars() {
+            let done = match c {
+                'x' => false,
+                'u' => {
+                    pid && status.state == RunitServiceState::Run
+                        && self.check_script(svc)
+                }
+                'd' => !pid && status.state == RunitServiceState::Down,
+                'C' => !pid || self.check_script(svc),
+                't' | 'k' => {
+                    (!pid && status.want == 'd')
+                        || (!changed_before_start
+                            && pid
+                            && !status.got_term
+                            && self.check_script(svc))
+                }
+                'o' => {
+                    !((!pid && changed_before_start)
+                        || (pid && status.want != 'd'))
+                }
+                _ => true,
+            };
+
+            if !done {
+                return 0;
+            }
+        }
+
+        println!("ok: {}", status_line(&svc.name, &svc.path, &status));
+        1
+    }
+
+    /// Run the `check` script of a service (if any), true if it succeeds.
+    fn check_script(&mut self, svc: &RunitService) -> bool {
+        let script = svc.path.join("check");
+        if let Err(err) = fs::metadata(&script) {
+            if err.kind() == io::ErrorKind::NotFound {
+                return true;
+            }
+            println!(
+                "warning: {}: unable to stat check script: {}",
+                svc.name,
+                error_str(&err)
+            );
+            return false;
+        }
+
+        // like sv, the check script's stdout goes to stderr
+        match Command::new("./check")
+            .current_dir(&svc.path)
+            .stdout(io::stderr())
+            .status()
+        {
+            Ok(status) => status.success(),
+            Err(err) => {
+                println!(
+                    "warning: {}: unable to run check script: {}",
+                    svc.name,
+                    error_str(&err)
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Format a status the same way `sv status` does.
+fn status_line(name: &str, path: &Path, status: &RunitStatus) -> String {
+    let normally_up = !path.join("down").exists();
+    let pid = status.pid.is_some();
+
+    let secs = status
+        .start_time
+        .and_then(|t| t.elapsed().ok())
+        .map_or(0, |d| d.as_secs());
+
+    let mut s = match status.state {
+        RunitServiceState::Down => "down: ".to_string(),
+        RunitServiceState::Run => "run: ".to_string(),
+        RunitServiceState::Finish => "finish: ".to_string(),
+        RunitServiceState::Unknown => String::new(),
+    };
+
+    s.push_str(name);
+    s.push_str(": ");
+
+    if status.state != RunitServiceState::Down {
+        s.push_str(&format!("(pid {}) ", status.pid.unwrap_or(0)));
+    }
+    s.push_str(&format!("{}s", secs));
+
+    if pid && !normally_up {
+        s.push_str(", normally down");
+    }
+    if !pid && normally_up {
+        s.push_str(", normally up");
+    }
+    if pid && status.paused {
+        s.push_str(", paused");
+    }
+    if !pid && status.want == 'u' {
+        s.push_str(", want up");
+    }
+    if pid && status.want == 'd' {
+        s.push_str(", want down");
+    }
+    if pid && status.got_term {
+        s.push_str(", got TERM");
+    }
+
+    s
+}
+
+/// Seconds since the epoch (runit only compares whole seconds).
+fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Print the usage message and return the exit code for it.
+fn usage() -> i32 {
+    eprintln!("usage: sv [-v] [-w sec] command service ...");
+    EXIT_USAGE
+}
+
+/// Parse the leading digits of a string like `scan_ulong(3)` (0 if none).
+fn scan_ulong(s: &str) -> u64 {
+    let digits: String = s.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().unwrap_or(0)
+}
+
+/// Describe an error the same way runit (`error_str(3)`) does.
+fn error_str(err: &io::Error) -> String {
+    let s = match err.raw_os_error() {
+        Some(libc::ENOENT) => "file does not exist",
+        Some(libc::EACCES) => "access denied",
+        Some(libc::EPERM) => "permission denied",
+        Some(libc::ENOTDIR) => "not a directory",
+        Some(libc::EISDIR) => "is a directory",
+        Some(libc::ENXIO) => "device not configured",
+        Some(libc::EROFS) => "read-only file system",
+        Some(libc::EINTR) => "interrupted system call",
+        Some(libc::EAGAIN) => "temporary failure",
+        Some(libc::ENOMEM) => "out of memory",
+        Some(libc::EIO) => "input/output error",
+        Some(libc::ELOOP) => "symbolic link loop",
+        Some(libc::ENAMETOOLONG) => "file name too long",
+        Some(libc::ENOSPC) => "out of disk space",
+        Some(libc::EBADF) => "file descriptor not open",
+        Some(libc::EEXIST) => "file already exists",
+        Some(libc::EINVAL) => "invalid argument",
+        Some(libc::EPIPE) => "broken pipe",
+        Some(code) => return format!("error {}", code),
+        None => match err.kind() {
+            io::ErrorKind::NotFound => "file does not exist",
+            io::ErrorKind::PermissionDenied => "access denied",
+            _ => return err.to_string(),
+        },
+    };
+
+    s.to_string()
+}
@@ -0,0 +1,152 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! `vsv undo`.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+
+use anyhow::{anyhow, bail, ensure, Result};
+use yansi::Paint;
+
+use crate::audit;
+use crate::config::Config;
+use crate::journal;
+use crate::journal::{JournalEntry, Recorder, ServiceAspect};
+use crate::runit::{RunitCommand, RunitService};
+
+/// Handle `vsv undo`.
+pub fn do_undo(cfg: &Config, list: bool) -> Result<()> {
+    if list {
+        return do_list(cfg);
+    }
+
+    let id = cfg.operands.first().map(String::as_str);
+    let tx = journal::find_transaction(cfg, id)?;
+
+    println!(
+        "{} transaction {} ({} at {})",
+        "undoing".bold(),
+        tx.id.bold(),
+        tx.command,
+        tx.timestamp.dim()
+    );
+
+    // undoing is itself a transaction so it can be undone as well
+    let recorder = Recorder::begin(cfg, &format!("undo {}", tx.id));
+    let mut had_error = false;
+
+    // restore in reverse order so the oldest state of each service wins
+    for entry in tx.entries.iter().rev() {
+        print!(
+            "{} service {} ({})... ",
+            "restoring".bold(),
+            entry.service.bold(),
+            entry.aspect
+        );
+
+        let svc = RunitService::new(&entry.service, &entry.path);
+        let previous = entry.aspect.current(&svc);
+
+        if let Some(ref previous) = previous {
+            recorder.snapshot(&svc, previous.clone());
+        }
+
+        let ret = restore(&svc, entry);
+
+        let previous = previous.map_or("n/a".to_string(), |a| a.to_string());
+        audit::record(cfg, "undo", &entry.service, &previous, &ret);
+
+        match ret {
+            Ok(()) => println!("{}", "done".green()),
+            Err(err) => {
+                println!("{}", format!("failed! {}", err).red());
+                had_error = true;
+            }
+        }
+    }
+
+    ensure!(!had_error, "failed to undo transaction {}", tx.id);
+
+    Ok(())
+}
+
+/// Handle `vsv undo --list`.
+fn do_list(cfg: &Config) -> Result<()> {
+    let transactions = journal::read_transactions(cfg)?;
+
+    println!(
+        "{}",
+        format!(
+            "{: <20} {: <20} {: <16} {}",
+            "TRANSACTION", "TIME", "COMMAND", "SERVICES"
+        )
+        .bold()
+    );
+
+    for tx in transactions {
+        let mut services: Vec<&str> =
+            tx.entries.iter().map(|e| e.service.as_str()).collect();
+        services.dedup();
+
+        println!(
+            "{} {} {: <16} {}",
+            format!("{: <20}", tx.id).bold(),
+            format!("{: <20}", tx.timestamp).dim(),
+            tx.command,
+            services.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Restore a single piece of service state from the journal.
+fn restore(svc: &RunitService, entry: &JournalEntry) -> Result<()> {
+    match &entry.aspect {
+        ServiceAspect::Link(None) => match fs::symlink_metadata(&svc.path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                fs::remove_file(&svc.path)?;
+            }
+            Ok(_) => bail!("{} is not a symlink", svc.path.display()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        },
+        ServiceAspect::Link(Some(target)) => match fs::read_link(&svc.path) {
+            Ok(link) if &link == target => (),
+            Ok(link) => bail!(
+                "conflict: {} now points to {}",
+                svc.path.display(),
+                link.display()
+            ),
+            Err(_) if fs::symlink_metadata(&svc.path).is_ok() => bail!(
+                "conflict: {} exists and is not a symlink",
+                svc.path.display()
+            ),
+            Err(_) => symlink(target, &svc.path)?,
+        },
+        ServiceAspect::Enabled(enabled) => {
+            if !svc.path.is_dir() {
+                return Err(anyhow!("service not found"));
+            }
+
+            if *enabled {
+                svc.enable()?;
+            } else {
+                svc.disable()?;
+            }
+        }
+        ServiceAspect::WantUp(up) => {
+            ensure!(svc.valid(), "service not valid");
+
+            let cmd = if *up { RunitCommand::Up } else { RunitCommand::Down };
+            svc.control(cmd)?;
+        }
+    }
+
+    Ok(())
+}
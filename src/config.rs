@@ -10,24 +10,53 @@
 
 use std::env;
 use std::fmt;
+use std::fs;
 use std::io;
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 
 use crate::arguments::{Args, Commands};
+use crate::health;
+use crate::runit;
+use crate::utils;
 
 // default values
 pub const DEFAULT_SVDIR: &str = "/var/service";
 pub const DEFAULT_PROC_DIR: &str = "/proc";
 pub const DEFAULT_USER_DIR: &str = "runit/service";
 pub const DEFAULT_AVAIL_DIR: &str = "/etc/sv"; // New constant
+pub const DEFAULT_USER_AVAIL_DIR: &str = "runit/sv";
+pub const CONFIG_FILE_NAME: &str = "config";
+pub const TEMPLATES_DIR_NAME: &str = "templates";
+pub const DEFAULT_SV_PROG: &str = "sv";
+pub const DEFAULT_AUDIT_LOG: &str = "/var/log/vsv/audit.log";
+pub const DEFAULT_USER_AUDIT_LOG: &str = "vsv/audit.log";
+pub const DEFAULT_JOURNAL: &str = "/var/lib/vsv/journal";
+pub const DEFAULT_USER_JOURNAL: &str = "vsv/journal";
+pub const DEFAULT_WAIT_SECS: u64 = 7;
+pub const DEFAULT_HEALTH_CACHE: &str = "/var/cache/vsv/health";
+pub const DEFAULT_USER_HEALTH_CACHE: &str = "vsv/health";
+pub const DEFAULT_XBPS_DBDIR: &str = "/var/db/xbps";
+pub const DEFAULT_RUNLEVEL_DIR: &str = "/etc/runit/runsvdir";
 
 // env var name
 pub const ENV_NO_COLOR: &str = "NO_COLOR";
 pub const ENV_SVDIR: &str = "SVDIR";
+pub const ENV_SVAVAIL: &str = "SVAVAIL";
+pub const ENV_CONFIG_DIR: &str = "VSV_CONFIG_DIR";
 pub const ENV_PROC_DIR: &str = "PROC_DIR";
+pub const ENV_SV_PROG: &str = "SV_PROG";
+pub const ENV_AUDIT_LOG: &str = "VSV_AUDIT_LOG";
+pub const ENV_AUDIT_SYSLOG: &str = "VSV_AUDIT_SYSLOG";
+pub const ENV_JOURNAL: &str = "VSV_JOURNAL";
+pub const ENV_SVWAIT: &str = "SVWAIT";
+pub const ENV_HEALTH_CACHE: &str = "VSV_HEALTH_CACHE";
+pub const ENV_HEALTH_TTL: &str = "VSV_HEALTH_TTL";
+pub const ENV_XBPS_DBDIR: &str = "VSV_XBPS_DBDIR";
+pub const ENV_RUNLEVEL_DIR: &str = "VSV_RUNLEVEL_DIR";
 
 /// vsv execution modes (subcommands).
 #[derive(Debug)]
@@ -40,6 +69,22 @@ pub enum ProgramMode {
     Add,    // New
     Remove, // New
     Avail,  // New
+    External,
+    Audit,
+    Undo,
+    Sv,
+    Action,
+    Check,
+    Prune,
+    New,
+    Clone,
+    Conf,
+    Env,
+    Cat,
+    Edit,
+    Lint,
+    Diff,
+    Runlevel,
 }
 
 impl fmt::Display for ProgramMode {
@@ -53,6 +98,22 @@ impl fmt::Display for ProgramMode {
             ProgramMode::Add => "add",
             ProgramMode::Remove => "remove",
             ProgramMode::Avail => "avail",
+            ProgramMode::External => "external",
+            ProgramMode::Audit => "audit",
+            ProgramMode::Undo => "undo",
+            ProgramMode::Sv => "sv",
+            ProgramMode::Action => "action",
+            ProgramMode::Check => "check",
+            ProgramMode::Prune => "prune",
+            ProgramMode::New => "new",
+            ProgramMode::Clone => "clone",
+            ProgramMode::Conf => "conf",
+            ProgramMode::Env => "env",
+            ProgramMode::Cat => "cat",
+            ProgramMode::Edit => "edit",
+            ProgramMode::Lint => "lint",
+            ProgramMode::Diff => "diff",
+            ProgramMode::Runlevel => "runlevel",
         };
 
         s.fmt(f)
@@ -64,12 +125,38 @@ pub struct Config {
     pub mode: ProgramMode,
     pub colorize: bool,
     pub svdir: PathBuf,
-    pub avail_dir: PathBuf, // New field
+    pub avail_dirs: Vec<PathBuf>,
+    pub config_dir: PathBuf,
     pub tree: bool,
     pub log: bool,
     pub verbose: usize,
     pub operands: Vec<String>,
     pub proc_path: PathBuf,
+    pub audit_log: PathBuf,
+    pub audit_syslog: bool,
+    pub journal: PathBuf,
+    pub health: bool,
+    pub package: bool,
+    pub json: bool,
+    pub health_cache: PathBuf,
+    pub health_ttl: Duration,
+    pub check_timeout: Duration,
+    pub now: bool,
+    pub wait: bool,
+    pub wait_timeout: Duration,
+    pub xbps_dbdir: PathBuf,
+    pub runlevel_dir: PathBuf,
+    pub root: Option<PathBuf>,
+    pub all_scopes: bool,
+    pub scopes: Vec<Scope>,
+}
+
+/// A service directory shown by `vsv status --all-scopes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    /// `system` or the name of the user.
+    pub name: String,
+    pub svdir: PathBuf,
 }
 
 impl Config {
@@ -77,17 +164,42 @@ impl Config {
         let mut tree = args.tree;
         let mut log = args.log;
         let mut operands = vec![];
+        let mut health = false;
+        let mut package = false;
+        let mut json = false;
+        let mut now = false;
+        let mut wait = false;
+        let mut check_timeout = health::DEFAULT_TIMEOUT;
+        let mut runlevel = None;
+        let mut all_scopes = false;
 
-        let svdir = get_svdir(&args.dir, args.user)
+        let mut svdir = get_svdir(&args.dir, args.user)
             .context("failed to determine SVDIR")?;
 
-        // Determine available directory (defaulting to /etc/sv)
-        let avail_dir = PathBuf::from(DEFAULT_AVAIL_DIR);
+        let config_dir = get_config_dir();
+        let config_file = ConfigFile::load(&config_dir.join(CONFIG_FILE_NAME))?;
+        let avail_dirs =
+            get_avail_dirs(args.avail_dir.as_deref(), args.user, &config_file)
+                .context("failed to determine avail dirs")?;
 
         // check mode
         let mode = if let Some(cmd) = &args.command {
             match cmd {
-                Commands::Status { tree: t, filter, log: l } => {
+                Commands::Status {
+                    tree: t,
+                    filter,
+                    log: l,
+                    health: h,
+                    package: p,
+                    json: j,
+                    runlevel: r,
+                    all_scopes: a,
+                } => {
+                    runlevel.clone_from(r);
+                    all_scopes = *a;
+                    health = *h;
+                    package = *p;
+                    json = *j;
                     if *t {
                         tree = true;
                     }
@@ -97,28 +209,103 @@ impl Config {
                     operands = filter.to_vec();
                     ProgramMode::Status
                 }
-                Commands::Enable { services } => {
+                Commands::Enable { now: n, wait: w, services } => {
                     operands = services.to_vec();
+                    now = *n;
+                    wait = *w;
                     ProgramMode::Enable
                 }
-                Commands::Disable { services } => {
+                Commands::Disable { now: n, wait: w, services } => {
                     operands = services.to_vec();
+                    now = *n;
+                    wait = *w;
                     ProgramMode::Disable
                 }
                 // New Commands
-                Commands::Add { services } => {
+                Commands::Add { wait: w, runlevel: r, services, .. } => {
+                    runlevel.clone_from(r);
                     operands = services.to_vec();
+                    wait = *w;
                     ProgramMode::Add
                 }
-                Commands::Remove { services } => {
+                Commands::Remove { wait: w, runlevel: r, services, .. } => {
+                    runlevel.clone_from(r);
                     operands = services.to_vec();
+                    wait = *w;
                     ProgramMode::Remove
                 }
-                Commands::Avail => ProgramMode::Avail,
+                Commands::Avail { .. } => ProgramMode::Avail,
+                Commands::Prune { .. } => ProgramMode::Prune,
+                Commands::Cat { service, .. } => {
+                    operands = vec![service.to_string()];
+                    ProgramMode::Cat
+                }
+                Commands::Diff { services } => {
+                    operands.clone_from(services);
+                    ProgramMode::Diff
+                }
+                Commands::Runlevel { .. } => ProgramMode::Runlevel,
+                Commands::Lint { services, .. } => {
+                    operands.clone_from(services);
+                    ProgramMode::Lint
+                }
+                Commands::Edit { service, .. } => {
+                    operands = vec![service.to_string()];
+                    ProgramMode::Edit
+                }
+                Commands::Conf { service, .. } => {
+                    operands = vec![service.to_string()];
+                    ProgramMode::Conf
+                }
+                Commands::Env { service, .. } => {
+                    operands = vec![service.to_string()];
+                    ProgramMode::Env
+                }
+                Commands::Clone { src, dst, .. } => {
+                    operands = vec![src.to_string(), dst.to_string()];
+                    ProgramMode::Clone
+                }
+                Commands::New { name, .. } => {
+                    operands = vec![name.to_string()];
+                    ProgramMode::New
+                }
                 Commands::Log { service, .. } => {
                     operands = vec![service.to_string()];
                     ProgramMode::Log
                 }
+                Commands::Audit { service, .. } => {
+                    operands = service.iter().cloned().collect();
+                    ProgramMode::Audit
+                }
+                Commands::Undo { transaction, .. } => {
+                    operands = transaction.iter().cloned().collect();
+                    ProgramMode::Undo
+                }
+                Commands::Sv { args } => {
+                    operands = args.to_vec();
+                    ProgramMode::Sv
+                }
+                Commands::Check { timeout, services } => {
+                    operands = services.to_vec();
+                    if let Some(timeout) = timeout {
+                        check_timeout = *timeout;
+                    }
+                    ProgramMode::Check
+                }
+                Commands::Action { service, .. } => {
+                    operands = vec![service.to_string()];
+                    ProgramMode::Action
+                }
+                Commands::External(args) => {
+                    operands = args.to_vec();
+
+                    // `vsv -t foo` and `vsv -l foo` are status filters
+                    if tree || log {
+                        ProgramMode::Status
+                    } else {
+                        ProgramMode::External
+                    }
+                }
                 _ => {
                     // Control commands
                     match cmd {
@@ -148,27 +335,352 @@ impl Config {
         };
 
         let colorize = should_colorize_output(&args.color)?;
-        let verbose = args.verbose;
+        let verbose = usize::from(args.verbose);
         let proc_path = env::var_os(ENV_PROC_DIR)
             .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from(DEFAULT_PROC_DIR));
+        let audit_log = get_state_file(
+            ENV_AUDIT_LOG,
+            DEFAULT_AUDIT_LOG,
+            DEFAULT_USER_AUDIT_LOG,
+        );
+        let journal =
+            get_state_file(ENV_JOURNAL, DEFAULT_JOURNAL, DEFAULT_USER_JOURNAL);
+        let audit_syslog = env::var_os(ENV_AUDIT_SYSLOG).is_some();
+        let wait_timeout = env::var(ENV_SVWAIT)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_WAIT_SECS);
+        let health_cache = get_state_file(
+            ENV_HEALTH_CACHE,
+            DEFAULT_HEALTH_CACHE,
+            DEFAULT_USER_HEALTH_CACHE,
+        );
+        let health_ttl = env::var(ENV_HEALTH_TTL)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(health::DEFAULT_CACHE_TTL);
+        let xbps_dbdir = env::var_os(ENV_XBPS_DBDIR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_XBPS_DBDIR));
+        let mut runlevel_dir = env::var_os(ENV_RUNLEVEL_DIR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_RUNLEVEL_DIR));
+
+        // `--root <dir>` looks up every path inside the target root, and
+        // `--target-pid <pid>` inside the root of a process (a container)
+        let (root, proc_path) = match (&args.root, args.target_pid) {
+            (Some(dir), _) => {
+                let root = fs::canonicalize(dir)
+                    .with_context(|| format!("failed to resolve {:?}", dir))?;
+                (Some(root), proc_path)
+            }
+            (None, Some(pid)) => {
+                (Some(get_pid_root(&proc_path, pid)?), DEFAULT_PROC_DIR.into())
+            }
+            (None, None) => (None, proc_path),
+        };
+        let (proc_path, xbps_dbdir, avail_dirs) = match &root {
+            Some(root) => {
+                runlevel_dir = utils::in_root(root, &runlevel_dir);
+                svdir = get_root_svdir(root, &svdir, &runlevel_dir);
+                (
+                    utils::in_root(root, &proc_path),
+                    utils::in_root(root, &xbps_dbdir),
+                    avail_dirs
+                        .iter()
+                        .map(|dir| utils::in_root(root, dir))
+                        .collect(),
+                )
+            }
+            None => (proc_path, xbps_dbdir, avail_dirs),
+        };
+
+        // `--runlevel <name>` acts on that runlevel's dir instead of SVDIR
+        if let Some(name) = runlevel {
+            ensure!(
+                args.dir.is_none() && !args.user,
+                "--runlevel can't be used with -d or -u"
+            );
+            svdir = runit::runlevel_path(&runlevel_dir, &name)?;
+        }
+
+        // only look for scopes if they are needed (`scope:name` operands)
+        ensure!(
+            !(all_scopes && args.user),
+            "--all-scopes can't be used with -u"
+        );
+        let scopes = if all_scopes
+            || operands.iter().any(|o| split_scope(o).is_some())
+        {
+            get_scopes(&config_file, &svdir, root.as_deref())?
+        } else {
+            vec![]
+        };
 
         let o = Self {
             mode,
             colorize,
             svdir,
-            avail_dir,
+            avail_dirs,
+            config_dir,
             tree,
             log,
             verbose,
             operands,
             proc_path,
+            audit_log,
+            audit_syslog,
+            journal,
+            health,
+            package,
+            json,
+            health_cache,
+            health_ttl: Duration::from_secs(health_ttl),
+            check_timeout: Duration::from_secs(check_timeout),
+            now,
+            wait,
+            wait_timeout: Duration::from_secs(wait_timeout),
+            xbps_dbdir,
+            runlevel_dir,
+            root,
+            all_scopes,
+            scopes,
         };
 
         Ok(o)
     }
 }
 
+impl Config {
+    /// Find the first avail dir in the search path that has the service.
+    pub fn find_avail(&self, name: &str) -> Option<PathBuf> {
+        self.avail_dirs.iter().map(|dir| dir.join(name)).find(|p| p.is_dir())
+    }
+
+    /**
+     * Find the real directory defining a service: the target of the svdir
+     * symlink (e.g. `/etc/sv/foo`) if it is added, otherwise the service in
+     * the avail dirs.
+     */
+    pub fn find_definition(&self, name: &str) -> Result<PathBuf> {
+        let path = self.svdir.join(name);
+        let path = if path.is_dir() {
+            path
+        } else {
+            self.find_avail(name).with_context(|| {
+                format!(
+                    "service {} not found in {:?} or avail dirs",
+                    name, self.svdir
+                )
+            })?
+        };
+
+        self.canonicalize(&path)
+            .with_context(|| format!("failed to resolve {:?}", path))
+    }
+
+    /**
+     * Resolve a path like `fs::canonicalize`, following symlinks inside the
+     * target root when using `--root` (a service symlink to `/etc/sv/foo` in
+     * an image points at the image's `/etc/sv/foo`, not the host's).
+     */
+    pub fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        match &self.root {
+            Some(root) => utils::canonicalize_in_root(root, path),
+            None => fs::canonicalize(path),
+        }
+    }
+
+    /**
+     * Get the path of a service in the svdir (see `resolve_service`), or in
+     * the svdir of another scope for `scope:name`.
+     */
+    pub fn service_path(&self, name: &str) -> Result<PathBuf> {
+        if let Some((scope, name)) = split_scope(name) {
+            let scope = self
+                .scopes
+                .iter()
+                .find(|s| s.name == scope)
+                .ok_or_else(|| anyhow!("unknown scope {:?}", scope))?;
+            return Ok(self.resolve_service(&scope.svdir.join(name)));
+        }
+
+        Ok(self.resolve_service(&self.svdir.join(name)))
+    }
+
+    /**
+     * Resolve the path of a service inside the target root when using
+     * `--root`, so an absolute service symlink in an image never leads to the
+     * host's services (and their supervise control pipes).
+     */
+    pub fn resolve_service(&self, path: &Path) -> PathBuf {
+        let Some(root) = &self.root else {
+            return path.to_path_buf();
+        };
+
+        if let Ok(path) = utils::canonicalize_in_root(root, path) {
+            return path;
+        }
+
+        // broken inside the root, but it may still exist on the host
+        match fs::read_link(path) {
+            Ok(target) if target.is_absolute() && path.exists() => {
+                utils::in_root(root, &target)
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Check if a path exists, following symlinks inside the target root
+    /// (see `canonicalize`).
+    pub fn exists(&self, path: &Path) -> bool {
+        self.canonicalize(path).is_ok()
+    }
+
+    /// Check if a path is a broken symlink, inside the target root if set.
+    pub fn is_broken_link(&self, path: &Path) -> bool {
+        runit::is_broken_link(path, self.root.as_deref())
+    }
+
+    /// Get the path to an absolute path inside the target root on the host.
+    pub fn rooted(&self, path: &Path) -> PathBuf {
+        match &self.root {
+            Some(root) => utils::in_root(root, path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Get the path as seen from inside the target root (see `--root`).
+    pub fn unrooted<'a>(&self, path: &'a Path) -> &'a Path {
+        self.root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+    }
+
+    /// The avail search path formatted like `$SVAVAIL`.
+    pub fn avail_path_string(&self) -> String {
+        let dirs: Vec<String> = self
+            .avail_dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect();
+        dirs.join(":")
+    }
+}
+
+/**
+ * The vsv config file (`~/.config/vsv/config`).
+ *
+ * Each line is `key = value`, blank lines and lines starting with `#` are
+ * ignored.
+ */
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    entries: Vec<(String, String)>,
+}
+
+impl ConfigFile {
+    /// Load the config file (a missing file is an empty config).
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read {:?}", path));
+            }
+        };
+
+        let mut entries = vec![];
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("{:?} line {}: expected key = value", path, i + 1);
+            };
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Get the value for a key (the last one wins).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/**
+ * Determine the vsv config directory (`VSV_CONFIG_DIR` or `~/.config/vsv`).
+ */
+pub fn get_config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os(ENV_CONFIG_DIR) {
+        return PathBuf::from(dir);
+    }
+
+    dirs::config_dir()
+        .map(|dir| dir.join("vsv"))
+        .unwrap_or_else(|| PathBuf::from("/etc/vsv"))
+}
+
+/**
+ * Determine the avail dirs search path.
+ *
+ * In order: `--avail-dir`, `SVAVAIL` env, `avail_dir` in the config file, and
+ * lastly `/etc/sv` (or `~/runit/sv` in user mode).  Each can be a
+ * colon-separated list of directories.
+ */
+pub fn get_avail_dirs(
+    avail_arg: Option<&str>,
+    user_arg: bool,
+    config_file: &ConfigFile,
+) -> Result<Vec<PathBuf>> {
+    let env_var = env::var(ENV_SVAVAIL).ok();
+    let search_path = avail_arg
+        .or(env_var.as_deref())
+        .or_else(|| config_file.get("avail_dir"));
+
+    if let Some(search_path) = search_path {
+        let dirs = split_search_path(search_path);
+        ensure!(!dirs.is_empty(), "empty avail dir search path");
+        return Ok(dirs);
+    }
+
+    if user_arg {
+        let home = env::var_os("HOME")
+            .context("failed to determine home directory (no HOME env var)")?;
+        return Ok(vec![PathBuf::from(home).join(DEFAULT_USER_AVAIL_DIR)]);
+    }
+
+    Ok(vec![PathBuf::from(DEFAULT_AVAIL_DIR)])
+}
+
+/**
+ * Split a colon-separated search path, expanding a leading `~/`.
+ */
+fn split_search_path(s: &str) -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+
+    s.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| match (dir.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(dir),
+        })
+        .collect()
+}
+
 /**
  * Check if the output should be colorized.
  */
@@ -219,3 +731,141 @@ fn get_svdir(dir_arg: &Option<PathBuf>, user_arg: bool) -> Result<PathBuf> {
     // default
     Ok(PathBuf::from(DEFAULT_SVDIR))
 }
+
+/**
+ * Split a `scope:name` service name, if the part before the colon looks like
+ * a scope (a user name or `system`).
+ */
+fn split_scope(name: &str) -> Option<(&str, &str)> {
+    let (scope, name) = name.split_once(':')?;
+    let valid = !scope.is_empty()
+        && scope.len() <= 32
+        && !scope.starts_with('-')
+        && scope
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c));
+
+    valid.then_some((scope, name))
+}
+
+/**
+ * Determine the scopes for `vsv status --all-scopes`.
+ *
+ * The `scopes` config entry is a colon-separated list of `name=dir`, by
+ * default the scopes are the system svdir and the `~/runit/service` of every
+ * user in the password database that has one.
+ */
+fn get_scopes(
+    config_file: &ConfigFile,
+    svdir: &Path,
+    root: Option<&Path>,
+) -> Result<Vec<Scope>> {
+    let rooted = |path: &Path| match root {
+        Some(root) => utils::in_root(root, path),
+        None => path.to_path_buf(),
+    };
+
+    if let Some(list) = config_file.get("scopes") {
+        let mut scopes = vec![];
+        for entry in list.split(':').filter(|s| !s.is_empty()) {
+            let Some((name, dir)) = entry.split_once('=') else {
+                bail!("invalid scope {:?} (expected name=dir)", entry);
+            };
+            let dir = split_search_path(dir).pop().unwrap_or_default();
+            scopes.push(Scope { name: name.to_string(), svdir: rooted(&dir) });
+        }
+        return Ok(scopes);
+    }
+
+    let mut scopes =
+        vec![Scope { name: "system".to_string(), svdir: svdir.to_path_buf() }];
+
+    let passwd = rooted(Path::new("/etc/passwd"));
+    let content = fs::read_to_string(&passwd)
+        .with_context(|| format!("failed to read {:?}", passwd))?;
+    for line in content.lines() {
+        // name:password:uid:gid:gecos:home:shell
+        let fields: Vec<&str> = line.split(':').collect();
+        let (Some(name), Some(home)) = (fields.first(), fields.get(5)) else {
+            continue;
+        };
+        if home.is_empty() || *home == "/" {
+            continue;
+        }
+
+        let dir = rooted(&Path::new(home).join(DEFAULT_USER_DIR));
+        if dir.is_dir() && !scopes.iter().any(|s| s.svdir == dir) {
+            scopes.push(Scope { name: name.to_string(), svdir: dir });
+        }
+    }
+
+    Ok(scopes)
+}
+
+/**
+ * Get the root directory of a process as seen by it (`/proc/<pid>/root`).
+ *
+ * This is a "magic" link that leads into the mount namespace of the process,
+ * so it must not be resolved (that would give its path in our namespace).
+ */
+fn get_pid_root(proc_path: &Path, pid: libc::pid_t) -> Result<PathBuf> {
+    let root = proc_path.join(pid.to_string()).join("root");
+    ensure!(
+        root.is_dir(),
+        "process {} not found (or not accessible) in {:?}",
+        pid,
+        proc_path
+    );
+
+    Ok(root)
+}
+
+/**
+ * Determine the svdir inside a target root (see `--root`).
+ *
+ * The svdir symlinks are followed inside the root.  On an image that hasn't
+ * booted `/var/service` usually points at `/run/runit/runsvdir/current` which
+ * doesn't exist yet, so the current runlevel is used instead.
+ */
+fn get_root_svdir(root: &Path, svdir: &Path, runlevel_dir: &Path) -> PathBuf {
+    let path = utils::in_root(root, svdir);
+    if let Ok(path) = utils::canonicalize_in_root(root, &path) {
+        return path;
+    }
+
+    if svdir == Path::new(DEFAULT_SVDIR) {
+        let current = runlevel_dir.join("current");
+        if let Ok(current) = utils::canonicalize_in_root(root, &current) {
+            return current;
+        }
+    }
+
+    path
+}
+
+/**
+ * Determine a state file (audit log, journal) to use.
+ *
+ * Root uses the system location (`system_default`), everyone else uses their
+ * own state directory (`~/.local/state/<user_default>`).
+ */
+fn get_state_file(
+    env_name: &str,
+    system_default: &str,
+    user_default: &str,
+) -> PathBuf {
+    // env var
+    if let Some(file) = env::var_os(env_name) {
+        return PathBuf::from(file);
+    }
+
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if !is_root {
+        if let Some(dir) = dirs::state_dir().or_else(dirs::data_local_dir) {
+            return dir.join(user_default);
+        }
+    }
+
+    // default
+    PathBuf::from(system_default)
+}
@@ -0,0 +1,242 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! Service health checks (the `check` script in a service directory).
+//!
+//! Results are cached on disk keyed by the service directory and the pid of
+//! the service, so repeated `vsv status --health` calls don't rerun every
+//! check script.
+
+use libc::pid_t;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use yansi::{Color, Style};
+
+use crate::runit::{RunitService, RunitServiceState};
+
+/// Default number of seconds a check script is allowed to run.
+pub const DEFAULT_TIMEOUT: u64 = 5;
+
+/// Default number of seconds a cached check result is valid for.
+pub const DEFAULT_CACHE_TTL: u64 = 30;
+
+/// Time to sleep between polling a running check script.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Result of a service health check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Health {
+    Healthy,
+    /// The check failed, with the reason why.
+    Unhealthy(String),
+    NoCheck,
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Health::Healthy => "healthy",
+            Health::Unhealthy(_) => "unhealthy",
+            Health::NoCheck => "no check",
+        };
+
+        s.fmt(f)
+    }
+}
+
+impl Health {
+    /// Style to use when printing this result.
+    pub fn style(&self) -> Style {
+        let style = Style::default();
+        match self {
+            Health::Healthy => style.fg(Color::Green),
+            Health::Unhealthy(_) => style.fg(Color::Red),
+            Health::NoCheck => style.dim(),
+        }
+    }
+}
+
+/**
+ * Run the `check` script for a service, killing it after `timeout`.
+ *
+ * A service with a check script that is not running is always unhealthy.
+ */
+pub fn run_check(svc: &RunitService, timeout: Duration) -> Health {
+    let script = svc.path.join("check");
+    if !script.is_file() {
+        return Health::NoCheck;
+    }
+
+    match svc.get_status() {
+        Ok(status) if status.state == RunitServiceState::Run => {}
+        _ => return Health::Unhealthy("not running".into()),
+    }
+
+    let mut child = match Command::new(&script)
+        .current_dir(&svc.path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => return Health::Unhealthy(err.to_string()),
+    };
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Health::Healthy,
+            Ok(Some(status)) => return Health::Unhealthy(status.to_string()),
+            Ok(None) => {}
+            Err(err) => return Health::Unhealthy(err.to_string()),
+        }
+
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Health::Unhealthy(format!(
+                "timed out after {}s",
+                timeout.as_secs()
+            ));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// A cached health check result.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    checked: u64,
+    pid: Option<pid_t>,
+    health: Health,
+}
+
+/// On-disk cache of health check results.
+#[derive(Debug)]
+pub struct HealthCache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl HealthCache {
+    /// Load the cache from disk (a missing or corrupt cache is empty).
+    pub fn load(path: &Path, ttl: Duration) -> Self {
+        let mut entries = HashMap::new();
+
+        if let Ok(content) = fs::read_to_string(path) {
+            for line in content.lines() {
+                if let Some((key, entry)) = parse_line(line) {
+                    entries.insert(key, entry);
+                }
+            }
+        }
+
+        Self { path: path.to_path_buf(), ttl, entries }
+    }
+
+    /// Get a still valid result for the service (same pid, within the ttl).
+    pub fn get(&self, svc: &RunitService) -> Option<Health> {
+        let entry = self.entries.get(&svc.path)?;
+        let pid = svc.get_status().ok().and_then(|status| status.pid);
+
+        let age = now().saturating_sub(entry.checked);
+        if entry.pid != pid || age >= self.ttl.as_secs() {
+            return None;
+        }
+
+        Some(entry.health.clone())
+    }
+
+    /// Store a result for the service.
+    pub fn insert(&mut self, svc: &RunitService, health: &Health) {
+        let pid = svc.get_status().ok().and_then(|status| status.pid);
+        let entry = CacheEntry { checked: now(), pid, health: health.clone() };
+        self.entries.insert(svc.path.clone(), entry);
+    }
+
+    /// Write the cache back to disk atomically.
+    pub fn save(&self) -> Result<()> {
+        let mut keys: Vec<&PathBuf> = self.entries.keys().collect();
+        keys.sort();
+
+        let mut content = String::new();
+        for key in keys {
+            let entry = &self.entries[key];
+            let pid = entry.pid.map_or("-".to_string(), |pid| pid.to_string());
+            let (state, reason) = match &entry.health {
+                Health::Healthy => ("healthy", ""),
+                Health::Unhealthy(reason) => ("unhealthy", reason.as_str()),
+                Health::NoCheck => ("none", ""),
+            };
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                key.display(),
+                entry.checked,
+                pid,
+                state,
+                reason.replace(['\t', '\n'], " ")
+            ));
+        }
+
+        if let Some(dir) = self.path.parent() {
+            match fs::create_dir_all(dir) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("failed to create dir {:?}", dir)
+                    });
+                }
+            }
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, content)
+            .with_context(|| format!("failed to write {:?}", tmp))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to rename {:?}", tmp))?;
+
+        Ok(())
+    }
+}
+
+/// Parse a single line of the cache file.
+fn parse_line(line: &str) -> Option<(PathBuf, CacheEntry)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 5 {
+        return None;
+    }
+
+    let checked = fields[1].parse().ok()?;
+    let pid = match fields[2] {
+        "-" => None,
+        pid => Some(pid.parse().ok()?),
+    };
+    let health = match fields[3] {
+        "healthy" => Health::Healthy,
+        "unhealthy" => Health::Unhealthy(fields[4].to_string()),
+        "none" => Health::NoCheck,
+        _ => return None,
+    };
+
+    Some((PathBuf::from(fields[0]), CacheEntry { checked, pid, health }))
+}
+
+/// Current time in seconds since the epoch.
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,308 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! Journal of pre-operation service state, used by `vsv undo`.
+//!
+//! Every invocation of vsv that modifies services is a single transaction.
+//! Before a service is touched, the part of its state that is about to change
+//! (symlink, `down` file, or wanted up/down state) is appended to the journal
+//! so it can be restored later.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time;
+
+use anyhow::{anyhow, bail, Context, Result};
+use yansi::Paint;
+
+use crate::config::Config;
+use crate::runit::RunitService;
+use crate::utils;
+use crate::utils::verbose;
+
+/// Number of tab-separated fields in a journal line.
+const NUM_FIELDS: usize = 7;
+
+/// A single piece of service state that can be restored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceAspect {
+    /// Symlink in the service directory (`None` if it did not exist).
+    Link(Option<PathBuf>),
+    /// Whether the service was enabled (no `down` file).
+    Enabled(bool),
+    /// Whether the service was wanted up (`true`) or down (`false`).
+    WantUp(bool),
+}
+
+impl fmt::Display for ServiceAspect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServiceAspect::Link(None) => write!(f, "not added"),
+            ServiceAspect::Link(Some(p)) => {
+                write!(f, "added ({})", p.display())
+            }
+            ServiceAspect::Enabled(true) => write!(f, "enabled"),
+            ServiceAspect::Enabled(false) => write!(f, "disabled"),
+            ServiceAspect::WantUp(true) => write!(f, "up"),
+            ServiceAspect::WantUp(false) => write!(f, "down"),
+        }
+    }
+}
+
+impl ServiceAspect {
+    /// Read the current state of the same kind from the given service.
+    pub fn current(&self, svc: &RunitService) -> Option<Self> {
+        match self {
+            ServiceAspect::Link(_) => {
+                Some(ServiceAspect::Link(fs::read_link(&svc.path).ok()))
+            }
+            ServiceAspect::Enabled(_) => {
+                Some(ServiceAspect::Enabled(svc.enabled()))
+            }
+            ServiceAspect::WantUp(_) => {
+                svc.want_up().map(ServiceAspect::WantUp)
+            }
+        }
+    }
+}
+
+/// A single journal entry.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub txid: String,
+    pub timestamp: String,
+    pub command: String,
+    pub service: String,
+    pub path: PathBuf,
+    pub aspect: ServiceAspect,
+}
+
+impl JournalEntry {
+    /// Parse an entry from a single line of the journal.
+    pub fn parse(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != NUM_FIELDS {
+            bail!("expected {} fields, found {}", NUM_FIELDS, fields.len());
+        }
+
+        let aspect = match (fields[5], fields[6]) {
+            ("link", "-") => ServiceAspect::Link(None),
+            ("link", target) => ServiceAspect::Link(Some(target.into())),
+            ("enabled", "yes") => ServiceAspect::Enabled(true),
+            ("enabled", "no") => ServiceAspect::Enabled(false),
+            ("want", "up") => ServiceAspect::WantUp(true),
+            ("want", "down") => ServiceAspect::WantUp(false),
+            (kind, value) => {
+                bail!("unknown service state: {} {:?}", kind, value)
+            }
+        };
+
+        Ok(Self {
+            txid: fields[0].to_string(),
+            timestamp: fields[1].to_string(),
+            command: fields[2].to_string(),
+            service: fields[3].to_string(),
+            path: PathBuf::from(fields[4]),
+            aspect,
+        })
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, value) = match &self.aspect {
+            ServiceAspect::Link(None) => ("link", "-".to_string()),
+            ServiceAspect::Link(Some(p)) => {
+                ("link", p.to_string_lossy().into_owned())
+            }
+            ServiceAspect::Enabled(b) => {
+                ("enabled", if *b { "yes" } else { "no" }.to_string())
+            }
+            ServiceAspect::WantUp(b) => {
+                ("want", if *b { "up" } else { "down" }.to_string())
+            }
+        };
+
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.txid,
+            self.timestamp,
+            self.command,
+            self.service,
+            self.path.display(),
+            kind,
+            value
+        )
+    }
+}
+
+/// A set of journal entries made by a single invocation of vsv.
+#[derive(Debug)]
+pub struct Transaction {
+    pub id: String,
+    pub timestamp: String,
+    pub command: String,
+    pub entries: Vec<JournalEntry>,
+}
+
+/// A transaction being recorded by the current invocation of vsv.
+pub struct Recorder<'a> {
+    cfg: &'a Config,
+    id: String,
+    command: String,
+}
+
+impl<'a> Recorder<'a> {
+    /// Start a new transaction for the given command.
+    pub fn begin(cfg: &'a Config, command: &str) -> Self {
+        let secs = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let id = format!("{}-{}", secs, process::id());
+
+        Self { cfg, id, command: command.to_string() }
+    }
+
+    /// Record the symlink state of the given service.
+    pub fn snapshot_link(&self, svc: &RunitService) {
+        self.snapshot(svc, ServiceAspect::Link(fs::read_link(&svc.path).ok()));
+    }
+
+    /// Record whether the given service is enabled.
+    pub fn snapshot_enabled(&self, svc: &RunitService) {
+        self.snapshot(svc, ServiceAspect::Enabled(svc.enabled()));
+    }
+
+    /// Record whether the given service is wanted up or down (if known).
+    pub fn snapshot_want(&self, svc: &RunitService) {
+        if let Some(want_up) = svc.want_up() {
+            self.snapshot(svc, ServiceAspect::WantUp(want_up));
+        }
+    }
+
+    /// Record the given state of a service.
+    pub fn snapshot(&self, svc: &RunitService, aspect: ServiceAspect) {
+        let entry = JournalEntry {
+            txid: self.id.clone(),
+            timestamp: utils::format_timestamp(time::SystemTime::now()),
+            command: self.command.clone(),
+            service: svc.name.clone(),
+            path: svc.path.clone(),
+            aspect,
+        };
+
+        if let Err(err) = append(&self.cfg.journal, &entry) {
+            eprintln!(
+                "{}: failed to write journal {:?}: {:#}",
+                "warning".yellow(),
+                self.cfg.journal,
+                err
+            );
+        }
+    }
+}
+
+/**
+ * Read all transactions from the journal, oldest first.
+ *
+ * Malformed or truncated lines (e.g. from a crash mid-write) are skipped with
+ * a warning in verbose mode instead of making every transaction unreachable.
+ */
+pub fn read_transactions(cfg: &Config) -> Result<Vec<Transaction>> {
+    let path = &cfg.journal;
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read journal {:?}", path))
+        }
+    };
+    let content = String::from_utf8_lossy(&content);
+
+    // entries from concurrent runs can be interleaved, so group them by
+    // transaction id (in order of first appearance)
+    let mut transactions: Vec<Transaction> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let entry = match JournalEntry::parse(line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                verbose!(
+                    cfg,
+                    "{:?}: line {}: skipping invalid entry: {:#}",
+                    path,
+                    i + 1,
+                    err
+                );
+                continue;
+            }
+        };
+
+        match index.get(&entry.txid) {
+            Some(&idx) => transactions[idx].entries.push(entry),
+            None => {
+                index.insert(entry.txid.clone(), transactions.len());
+                transactions.push(Transaction {
+                    id: entry.txid.clone(),
+                    timestamp: entry.timestamp.clone(),
+                    command: entry.command.clone(),
+                    entries: vec![entry],
+                });
+            }
+        }
+    }
+
+    Ok(transactions)
+}
+
+/// Find a transaction by id, or the most recent one if `id` is `None`.
+pub fn find_transaction(cfg: &Config, id: Option<&str>) -> Result<Transaction> {
+    let mut transactions = read_transactions(cfg)?;
+
+    let idx = match id {
+        Some(id) => transactions
+            .iter()
+            .position(|tx| tx.id == id)
+            .ok_or_else(|| anyhow!("transaction {} not found", id))?,
+        None => transactions
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("no transactions in {:?}", cfg.journal))?,
+    };
+
+    Ok(transactions.swap_remove(idx))
+}
+
+/// Append a single entry to the journal, creating it if needed.
+fn append(path: &Path, entry: &JournalEntry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {:?}", dir))?;
+    }
+
+    let mut f =
+        OpenOptions::new().create(true).append(true).mode(0o640).open(path)?;
+
+    writeln!(f, "{}", entry)?;
+
+    Ok(())
+}
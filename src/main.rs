@@ -13,7 +13,9 @@
 #![allow(clippy::uninlined_format_args)]
 
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::io::Write;
+use std::path::Path;
 
 use anyhow::{Context, Result};
 use clap::builder::{PossibleValue, PossibleValuesParser};
@@ -22,12 +24,17 @@ use clap_complete::env::CompleteEnv;
 use yansi::Paint;
 
 mod arguments;
+mod audit;
 mod commands;
 mod config;
 mod die;
+mod health;
+mod journal;
 mod runit;
 mod service;
+mod sha256;
 mod utils;
+mod xbps;
 
 use arguments::Commands;
 use config::Config;
@@ -62,6 +69,7 @@ fn build_cli_with_completions() -> Command {
         "remove",
         "enable",
         "disable",
+        "check",
     ];
 
     for sub_name in running_svc_cmds {
@@ -82,10 +90,11 @@ fn build_cli_with_completions() -> Command {
         });
     }
 
-    // 3. Attach running services to Log command (uses "service" singular argument)
-    {
+    // 3. Attach running services to Log and Action commands (uses "service"
+    // singular argument)
+    for sub_name in ["log", "action"] {
         let values = running_services.clone();
-        cmd = cmd.mut_subcommand("log", move |sub| {
+        cmd = cmd.mut_subcommand(sub_name, move |sub| {
             sub.mut_arg("service", move |arg| {
                 let vals = values.iter().map(|s| {
                     let static_str: &'static str =
@@ -169,8 +178,10 @@ fn do_main() -> Result<()> {
         cfg.colorize
     );
 
-    // check for root permissions
-    check_root_permissions(&cfg);
+    // check for root permissions (sv mode reports errors the same way sv does)
+    if !matches!(cfg.mode, config::ProgramMode::Sv) {
+        check_root_permissions(&cfg);
+    }
 
     // figure out subcommand to run
     if let Some(ref cmd) = args.command {
@@ -182,12 +193,81 @@ fn do_main() -> Result<()> {
             Commands::Disable { .. } => {
                 commands::enable_disable::do_disable(&cfg)
             }
-            Commands::Add { .. } => commands::add_remove::do_add(&cfg),
-            Commands::Remove { .. } => commands::add_remove::do_remove(&cfg),
-            Commands::Avail => commands::add_remove::do_avail(&cfg),
+            Commands::Add { start, down, .. } => {
+                commands::add_remove::do_add(&cfg, &cfg.operands, *start, *down)
+            }
+            Commands::Remove { stop, .. } => {
+                commands::add_remove::do_remove(&cfg, *stop)
+            }
+            Commands::Avail { search, added, not_added, modified, json } => {
+                let opts = commands::add_remove::AvailOptions {
+                    search: search.as_deref(),
+                    added: *added,
+                    not_added: *not_added,
+                    modified: *modified,
+                    json: *json,
+                };
+                commands::add_remove::do_avail(&cfg, &opts)
+            }
+            Commands::Audit { user, .. } => {
+                commands::audit::do_audit(&cfg, user.as_deref())
+            }
+            Commands::Undo { list, .. } => commands::undo::do_undo(&cfg, *list),
+            Commands::Cat { service, reveal } => {
+                commands::cat::do_cat(&cfg, service, *reveal)
+            }
+            Commands::Diff { .. } => commands::diff::do_diff(&cfg),
+            Commands::Lint { json, .. } => commands::lint::do_lint(&cfg, *json),
+            Commands::Runlevel { action } => {
+                commands::runlevel::do_runlevel(&cfg, action.as_ref())
+            }
+            Commands::Edit { service, file, force } => {
+                commands::edit::do_edit(&cfg, service, file.as_deref(), *force)
+            }
+            Commands::Conf { service, restart, action } => {
+                commands::conf::do_conf(
+                    &cfg,
+                    service,
+                    action.as_ref(),
+                    *restart,
+                )
+            }
+            Commands::Env { service, reveal, action } => {
+                commands::env::do_env(&cfg, service, action.as_ref(), *reveal)
+            }
+            Commands::Clone { src, dst, add } => {
+                commands::clone::do_clone(&cfg, src, dst, *add)
+            }
+            Commands::New { name, user, finish, check, log, add, command } => {
+                let opts = commands::new::NewOptions {
+                    user: user.as_deref(),
+                    finish: finish.as_deref(),
+                    check: check.as_deref(),
+                    log: *log,
+                    add: *add,
+                };
+                commands::new::do_new(&cfg, name, command, &opts)
+            }
+            Commands::Prune { yes, dry_run } => {
+                commands::prune::do_prune(&cfg, *yes, *dry_run)
+            }
+            Commands::Check { .. } => commands::check::do_check(&cfg),
+            Commands::Action { service, action, args } => {
+                commands::action::do_action(
+                    &cfg,
+                    service,
+                    action.as_deref(),
+                    args,
+                )
+            }
+            Commands::Sv { .. } => {
+                let code = commands::sv::do_sv(&cfg, &cfg.operands);
+                io::stdout().flush()?;
+                die!(code);
+            }
             Commands::Log { service, lines, all } => {
                 // Log command logic
-                let svdir_log = cfg.svdir.join(service).join("log");
+                let svdir_log = cfg.service_path(service)?.join("log");
                 let log_current = svdir_log.join("current");
 
                 let num_lines = lines.unwrap_or(10);
@@ -251,7 +331,7 @@ fn do_main() -> Result<()> {
                             ];
 
                             for sys_log_path_str in syslogs {
-                                let p = PathBuf::from(sys_log_path_str);
+                                let p = cfg.rooted(Path::new(sys_log_path_str));
                                 if p.exists() {
                                     println!(
                                         "{} {} in {} ({} lines)...",
@@ -284,6 +364,12 @@ fn do_main() -> Result<()> {
                 Ok(())
             }
             Commands::Completions { .. } => Ok(()), // Handled above
+            Commands::External(_) => match cfg.mode {
+                config::ProgramMode::Status => {
+                    commands::status::do_status(&cfg)
+                }
+                _ => commands::external::do_external(&cfg),
+            },
             _ => commands::control::run(&cfg, cmd),
         }
     } else {
@@ -293,13 +379,22 @@ fn do_main() -> Result<()> {
 }
 
 fn check_root_permissions(cfg: &Config) {
-    if cfg.svdir.to_str() == Some(config::DEFAULT_SVDIR) {
+    // the same dirs inside a target root (e.g. an image that is being
+    // prepared by a regular user) only need to be writable
+    if cfg.root.is_some() && utils::is_writable(&cfg.svdir) {
+        return;
+    }
+
+    let svdir = cfg.unrooted(&cfg.svdir);
+    if svdir.to_str() == Some(config::DEFAULT_SVDIR)
+        || svdir.starts_with(config::DEFAULT_RUNLEVEL_DIR)
+    {
         let is_root = unsafe { libc::geteuid() } == 0;
         if !is_root {
             die!(
                 1,
                 "Root permissions required to manage services in {}. Please run with sudo.",
-                config::DEFAULT_SVDIR
+                cfg.svdir.display()
             );
         }
     }
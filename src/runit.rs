@@ -8,15 +8,20 @@
 
 use libc::pid_t;
 use std::convert::TryInto;
+use std::fmt;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io;
 use std::io::Read;
 use std::io::Write;
+use std::os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::utils;
 
 /// Possible states for a runit service.
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Copy, Clone)]
@@ -27,6 +32,19 @@ pub enum RunitServiceState {
     Unknown,
 }
 
+impl fmt::Display for RunitServiceState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RunitServiceState::Run => "run",
+            RunitServiceState::Down => "down",
+            RunitServiceState::Finish => "finish",
+            RunitServiceState::Unknown => "n/a",
+        };
+
+        s.fmt(f)
+    }
+}
+
 /// Control commands (merged from rsv logic)
 #[derive(Debug, Copy, Clone)]
 pub enum RunitCommand {
@@ -42,10 +60,34 @@ pub enum RunitCommand {
     Term,
     Kill,
     Exit,
+    Usr1,
+    Usr2,
 }
 
 impl RunitCommand {
-    pub fn to_char(&self) -> char {
+    pub fn from_char(c: char) -> Option<Self> {
+        let cmd = match c {
+            'u' => RunitCommand::Up,
+            'd' => RunitCommand::Down,
+            'o' => RunitCommand::Once,
+            'p' => RunitCommand::Pause,
+            'c' => RunitCommand::Cont,
+            'h' => RunitCommand::Hup,
+            'a' => RunitCommand::Alarm,
+            'i' => RunitCommand::Interrupt,
+            'q' => RunitCommand::Quit,
+            't' => RunitCommand::Term,
+            'k' => RunitCommand::Kill,
+            'x' => RunitCommand::Exit,
+            '1' => RunitCommand::Usr1,
+            '2' => RunitCommand::Usr2,
+            _ => return None,
+        };
+
+        Some(cmd)
+    }
+
+    pub fn to_char(self) -> char {
         match self {
             RunitCommand::Up => 'u',
             RunitCommand::Down => 'd',
@@ -59,6 +101,8 @@ impl RunitCommand {
             RunitCommand::Term => 't',
             RunitCommand::Kill => 'k',
             RunitCommand::Exit => 'x',
+            RunitCommand::Usr1 => '1',
+            RunitCommand::Usr2 => '2',
         }
     }
 }
@@ -71,6 +115,7 @@ pub struct RunitStatus {
     pub start_time: Option<time::SystemTime>,
     pub want: char,
     pub paused: bool,
+    pub got_term: bool,
 }
 
 /**
@@ -123,22 +168,33 @@ impl RunitService {
 
     /// Send a control command to the service pipe.
     pub fn control(&self, cmd: RunitCommand) -> Result<()> {
+        self.control_all(&[cmd])
+    }
+
+    /// Send multiple control commands to the service pipe in a single write.
+    pub fn control_all(&self, cmds: &[RunitCommand]) -> Result<()> {
         let pipe_path = self.path.join("supervise").join("control");
 
         // rsv checks for supervise/ok, but supervise/control existing usually implies ok.
         if !pipe_path.exists() {
-            return Err(anyhow!(
-                "control pipe does not exist (service not supervised?)"
-            ));
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "control pipe does not exist (service not supervised?)",
+            )
+            .into());
         }
 
-        let mut f =
-            OpenOptions::new().write(true).open(&pipe_path).with_context(
-                || format!("failed to open control pipe {:?}", pipe_path),
-            )?;
-
-        let c = cmd.to_char();
-        f.write_all(&[c as u8])?;
+        // don't block forever if runsv isn't running (nothing reading the pipe)
+        let mut f = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&pipe_path)
+            .with_context(|| {
+                format!("failed to open control pipe {:?}", pipe_path)
+            })?;
+
+        let buf: Vec<u8> = cmds.iter().map(|cmd| cmd.to_char() as u8).collect();
+        f.write_all(&buf)?;
         Ok(())
     }
 
@@ -161,6 +217,7 @@ impl RunitService {
 
         let paused = buf[16] == 1;
         let want = buf[17] as char;
+        let got_term = buf[18] == 1;
 
         let tai = u64::from_be_bytes(buf[0..8].try_into()?);
         let offset = 4611686018427387914u64;
@@ -172,7 +229,140 @@ impl RunitService {
             None
         };
 
-        Ok(RunitStatus { state, pid, start_time, want, paused })
+        Ok(RunitStatus { state, pid, start_time, want, paused, got_term })
+    }
+
+    /// Get the current state of the service as a string ("n/a" if unknown).
+    pub fn state_string(&self) -> String {
+        match self.get_status() {
+            Ok(status) => status.state.to_string(),
+            Err(_) => RunitServiceState::Unknown.to_string(),
+        }
+    }
+
+    /// Check if the service is wanted up (`None` if the status is unknown).
+    pub fn want_up(&self) -> Option<bool> {
+        match self.get_status().ok()?.want {
+            'u' => Some(true),
+            'd' => Some(false),
+            _ => None,
+        }
+    }
+
+    /**
+     * Get a one line description of the service from its `description` file,
+     * or the first comment (after the shebang) at the top of `run`.
+     */
+    pub fn description(&self) -> Option<String> {
+        if let Ok(s) = fs::read_to_string(self.path.join("description")) {
+            let line = s.lines().map(str::trim).find(|l| !l.is_empty());
+            if let Some(line) = line {
+                return Some(line.to_string());
+            }
+        }
+
+        let script = fs::read_to_string(self.path.join("run")).ok()?;
+        script
+            .lines()
+            .skip_while(|line| line.starts_with("#!"))
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| line.trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    }
+
+    /**
+     * Find the envdir the run script passes to `chpst -e` (or `envdir`),
+     * resolved relative to the service directory.
+     */
+    pub fn envdir(&self) -> Option<PathBuf> {
+        let script = fs::read_to_string(self.path.join("run")).ok()?;
+        let dir = find_envdir(&script)?;
+
+        Some(self.path.join(dir))
+    }
+
+    /// List the custom actions (`actions/*`) for the service and whether each
+    /// one is executable.
+    pub fn actions(&self) -> Result<Vec<(String, bool)>> {
+        let dir = self.path.join("actions");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(vec![]);
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read dir {:?}", dir));
+            }
+        };
+
+        let mut actions = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let meta = fs::metadata(entry.path())?;
+            if !meta.is_file() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let executable = meta.permissions().mode() & 0o111 != 0;
+            actions.push((name, executable));
+        }
+
+        actions.sort();
+
+        Ok(actions)
+    }
+
+    /// Check if the service has an executable custom action with this name.
+    pub fn has_action(&self, action: &str) -> bool {
+        self.actions()
+            .map(|actions| actions.contains(&(action.to_string(), true)))
+            .unwrap_or(false)
+    }
+
+    /**
+     * Run a custom action (`actions/<name>`) for the service.
+     *
+     * The action is run from the service directory with the environment from
+     * the service `env/` directory applied (like `chpst -e`).
+     */
+    pub fn run_action(&self, action: &str, args: &[String]) -> Result<()> {
+        if action.is_empty() || action.contains('/') || action.starts_with('.')
+        {
+            return Err(anyhow!("invalid action name: {:?}", action));
+        }
+
+        let script = self.path.join("actions").join(action);
+        if !script.is_file() {
+            return Err(anyhow!("no such action: {}", action));
+        }
+
+        let mut cmd = Command::new(&script);
+        cmd.args(args).current_dir(&self.path);
+
+        let envdir = self.path.join("env");
+        if envdir.is_dir() {
+            for (key, value) in utils::read_envdir(&envdir)? {
+                match value {
+                    Some(value) => cmd.env(key, value),
+                    None => cmd.env_remove(key),
+                };
+            }
+        }
+
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute {:?}", script))?;
+
+        if !status.success() {
+            return Err(anyhow!("action {} exited with {}", action, status));
+        }
+
+        Ok(())
     }
 
     /// Get status of the associated log service if it exists
@@ -195,17 +385,25 @@ impl RunitService {
 }
 
 /**
- * List the services in a given runit service directory.
+ * List the services in a given runit service directory.  Symlinks are
+ * followed inside `root` if set (see `--root`).
  */
 pub fn get_services<T>(
     path: &Path,
     log: bool,
     filter: Option<T>,
+    root: Option<&Path>,
 ) -> Result<Vec<RunitService>>
 where
     T: AsRef<str>,
 {
     let mut dirs = Vec::new();
+    let is_dir = |p: &Path| match root {
+        Some(root) => {
+            utils::canonicalize_in_root(root, p).is_ok_and(|p| p.is_dir())
+        }
+        None => p.is_dir(),
+    };
 
     for entry in fs::read_dir(path)
         .with_context(|| format!("failed to read dir {:?}", path))?
@@ -213,7 +411,8 @@ where
         let entry = entry?;
         let p = entry.path();
 
-        if !p.is_dir() {
+        // broken symlinks are kept so they can be shown (and removed)
+        if !is_dir(&p) && !is_broken_link(&p, root) {
             continue;
         }
 
@@ -235,7 +434,7 @@ where
 
         if log {
             let p = entry.path().join("log");
-            if p.exists() {
+            if is_dir(&p) {
                 let name = "- log";
                 let service = RunitService::new(name, &p);
                 dirs.push(service);
@@ -247,3 +446,229 @@ where
 
     Ok(dirs)
 }
+
+/**
+ * Find the envdir argument in a run script: `chpst -e dir` (also `-edir` and
+ * `-e` combined with other options) or `envdir dir`.
+ */
+pub fn find_envdir(script: &str) -> Option<PathBuf> {
+    // chpst options that take an argument (in chpst(8) order, `-C` is from
+    // runit 2.2)
+    const ARG_OPTS: &str = "uUbe/CnlLmdopfcrt";
+
+    for line in script.lines() {
+        let words: Vec<&str> = line
+            .split_whitespace()
+            .take_while(|word| !word.starts_with('#'))
+            .map(|word| word.trim_matches(|c| c == '"' || c == '\''))
+            .collect();
+
+        for (i, word) in words.iter().enumerate() {
+            let cmd = word.rsplit('/').next().unwrap_or(word);
+            if cmd == "envdir" {
+                return words.get(i + 1).map(PathBuf::from);
+            }
+            if cmd != "chpst" {
+                continue;
+            }
+
+            let mut args = words[i + 1..].iter();
+            while let Some(arg) = args.next() {
+                let Some(flags) = arg.strip_prefix('-') else {
+                    break;
+                };
+
+                // options are grouped like `-ve ./env` or `-e./env`
+                for (j, c) in flags.char_indices() {
+                    if !ARG_OPTS.contains(c) {
+                        continue;
+                    }
+
+                    let rest = &flags[j + c.len_utf8()..];
+                    let value = if rest.is_empty() {
+                        args.next().copied()
+                    } else {
+                        Some(rest)
+                    };
+
+                    if c == 'e' {
+                        return value.map(PathBuf::from);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/**
+ * Check if a path is a symlink that points to something that doesn't exist
+ * (for example, the package providing a service was removed).  Absolute
+ * targets are resolved inside `root` if set (see `--root`).
+ */
+pub fn is_broken_link(path: &Path, root: Option<&Path>) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => match root {
+            Some(root) => utils::canonicalize_in_root(root, path).is_err(),
+            None => !path.exists(),
+        },
+        _ => false,
+    }
+}
+
+/// Links in the runlevel dir that aren't runlevels themselves.
+const RUNLEVEL_LINKS: &[&str] = &["current", "previous", "current.new"];
+
+/**
+ * List the runlevels (every directory in the runlevel dir, like `default` and
+ * `single`), sorted by name.
+ */
+pub fn get_runlevels(dir: &Path) -> Result<Vec<String>> {
+    let mut runlevels = vec![];
+
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to read dir {:?}", dir))?
+    {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+
+        if RUNLEVEL_LINKS.contains(&name.as_str()) || !entry.path().is_dir() {
+            continue;
+        }
+
+        runlevels.push(name);
+    }
+
+    runlevels.sort();
+
+    Ok(runlevels)
+}
+
+/// Get the name of the current runlevel (where `current` points).
+pub fn current_runlevel(dir: &Path) -> Option<String> {
+    let target = fs::read_link(dir.join("current")).ok()?;
+    let name = target.file_name()?.to_str()?;
+
+    Some(name.to_string())
+}
+
+/// Get the directory of a runlevel, making sure it exists.
+pub fn runlevel_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    ensure!(
+        !name.is_empty()
+            && !name.contains('/')
+            && !name.starts_with('.')
+            && !RUNLEVEL_LINKS.contains(&name),
+        "invalid runlevel name: {:?}",
+        name
+    );
+
+    let path = dir.join(name);
+    ensure!(
+        path.is_dir(),
+        "runlevel {} not found in {:?} (available: {})",
+        name,
+        dir,
+        get_runlevels(dir).unwrap_or_default().join(", ")
+    );
+
+    Ok(path)
+}
+
+/**
+ * Split a service name into its template and instance names, e.g.
+ * `openvpn@site-a` is an instance of the template `openvpn@`.
+ */
+pub fn split_instance(name: &str) -> Option<(&str, &str)> {
+    let (base, instance) = name.split_once('@')?;
+    if base.is_empty() || instance.is_empty() {
+        return None;
+    }
+
+    Some((base, instance))
+}
+
+/**
+ * Create the service directory for a template instance: a symlink farm next
+ * to the template (`openvpn@` -> `openvpn@site-a`).
+ *
+ * Every file in the template is symlinked, except `supervise` (each instance
+ * needs its own).  `log` and `env` are real directories so they can be
+ * supervised and extended, and the instance name is written to
+ * `env/INSTANCE` for the run script (e.g. via `chpst -e ./env`).  The links
+ * are relative if `relative` is set (for a target root, see `--root`).
+ */
+pub fn instantiate(
+    template: &Path,
+    instance: &str,
+    relative: bool,
+) -> Result<PathBuf> {
+    let name = template
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow!("{:?}: invalid template name", template))?;
+    let dir = template.with_file_name(format!("{}{}", name, instance));
+
+    fs::create_dir(&dir)
+        .with_context(|| format!("failed to create {:?}", dir))?;
+    link_farm(template, &dir, relative)?;
+
+    let env = dir.join("env");
+    fs::create_dir_all(&env)?;
+
+    // the template may have its own INSTANCE file, which is linked in above:
+    // replace the link rather than writing through it into the template
+    let file = env.join("INSTANCE");
+    match fs::remove_file(&file) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            return Err(err)
+                .with_context(|| format!("failed to remove {:?}", file));
+        }
+        _ => (),
+    }
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file)
+        .with_context(|| format!("failed to create {:?}", file))?;
+    writeln!(f, "{}", instance)?;
+
+    Ok(dir)
+}
+
+/**
+ * Symlink everything in `src` into `dst` (see `instantiate`), with relative
+ * links if `relative` is set.
+ */
+fn link_farm(src: &Path, dst: &Path, relative: bool) -> Result<()> {
+    for entry in fs::read_dir(src)
+        .with_context(|| format!("failed to read dir {:?}", src))?
+    {
+        let entry = entry?;
+        let name = entry.file_name();
+        let path = entry.path();
+
+        if name == "supervise" {
+            continue;
+        }
+
+        if (name == "log" || name == "env") && path.is_dir() {
+            let sub = dst.join(&name);
+            fs::create_dir(&sub)?;
+            link_farm(&path, &sub, relative)?;
+            continue;
+        }
+
+        if relative {
+            symlink(utils::relative_path(dst, &path), dst.join(&name))?;
+        } else {
+            symlink(&path, dst.join(&name))?;
+        }
+    }
+
+    Ok(())
+}
@@ -8,14 +8,17 @@
 
 use libc::pid_t;
 use std::fmt;
+use std::fs;
 use std::path::Path;
 use std::time;
 
 use anyhow::{anyhow, Result};
 use yansi::{Color, Style};
 
+use crate::health::Health;
 use crate::runit::{RunitService, RunitServiceState, RunitStatus};
 use crate::utils;
+use crate::xbps::Package;
 
 /// Possible states for a service.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -23,6 +26,7 @@ pub enum ServiceState {
     Run,
     Down,
     Finish,
+    Broken,
     Unknown,
 }
 
@@ -32,6 +36,7 @@ impl fmt::Display for ServiceState {
             ServiceState::Run => "run",
             ServiceState::Down => "down",
             ServiceState::Finish => "finish",
+            ServiceState::Broken => "broken",
             ServiceState::Unknown => "n/a",
         };
 
@@ -51,22 +56,49 @@ pub struct Service {
     paused: bool,
     log_status: Option<(RunitStatus, bool)>, // (status, enabled)
     print_log_column: bool,
+    health: Option<Health>,
+    package: Option<Option<Package>>, // Some(None) means no owning package
+    scope: Option<String>,
 }
 
 impl Service {
+    /**
+     * Gather the status of a service.  A `broken` service (a symlink to
+     * nothing) is never looked into, so a link in a `--root` image can't lead
+     * to a service on the host.
+     */
     pub fn from_runit_service(
         service: &RunitService,
+        broken: bool,
         want_pstree: bool,
         want_log_status: bool,
         proc_path: &Path,
     ) -> (Self, Vec<String>) {
         let mut messages: Vec<String> = vec![];
         let name = service.name.to_string();
-        let enabled = service.enabled();
+        // a broken link has no down file
+        let enabled = broken || service.enabled();
 
-        let status_result = service.get_status();
+        let status_result = if broken {
+            Err(anyhow!("broken symlink"))
+        } else {
+            service.get_status()
+        };
 
         let (state, pid, start_time, want, paused) = match status_result {
+            Err(_) if broken => {
+                let target = fs::read_link(&service.path)
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                messages.push(format!("broken symlink to {}", target));
+                (
+                    ServiceState::Broken,
+                    None,
+                    Err(anyhow!("broken symlink")),
+                    ' ',
+                    false,
+                )
+            }
             Ok(status) => {
                 let state = match status.state {
                     RunitServiceState::Run => ServiceState::Run,
@@ -95,7 +127,7 @@ impl Service {
             }
         };
 
-        let log_status = if want_log_status {
+        let log_status = if want_log_status && !broken {
             match service.get_log_status() {
                 Ok(status) => Some((status, service.log_running())),
                 Err(err) => {
@@ -148,11 +180,116 @@ impl Service {
             paused,
             log_status,
             print_log_column: want_log_status,
+            health: None,
+            package: None,
+            scope: None,
         };
 
         (svc, messages)
     }
 
+    /// Override the name shown in the SERVICE column.
+    pub fn set_display_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Set the health check result to show in the HEALTH column.
+    pub fn set_health(&mut self, health: Health) {
+        self.health = Some(health);
+    }
+
+    /// Set the xbps package that installed the service, to show in the
+    /// PACKAGE column.
+    pub fn set_package(&mut self, package: Option<Package>) {
+        self.package = Some(package);
+    }
+
+    /// Set the scope (`system` or a user) the service was found in.
+    pub fn set_scope(&mut self, scope: &str) {
+        self.scope = Some(scope.to_string());
+    }
+
+    fn format_scope(&self) -> Option<(String, Style)> {
+        let style = Style::default();
+        self.scope.as_ref().map(|scope| match scope.as_str() {
+            "system" => (scope.clone(), style.fg(Color::Magenta)),
+            _ => (scope.clone(), style.fg(Color::Cyan)),
+        })
+    }
+
+    fn format_package(&self) -> Option<(String, Style)> {
+        self.package.as_ref().map(|package| match package {
+            Some(pkg) => (pkg.pkgver.clone(), Style::default().fg(Color::Blue)),
+            None => ("---".to_string(), Style::default().dim()),
+        })
+    }
+
+    /// Format the service as a JSON object.
+    pub fn to_json(&self) -> String {
+        let opt_num =
+            |n: Option<u64>| n.map_or("null".into(), |n| n.to_string());
+
+        let uptime = self
+            .start_time
+            .as_ref()
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .map(|t| t.as_secs());
+
+        let log = match &self.log_status {
+            Some((status, enabled)) if self.print_log_column => format!(
+                "{{\"pid\":{},\"enabled\":{}}}",
+                opt_num(status.pid.map(|pid| pid as u64)),
+                enabled
+            ),
+            _ => "null".to_string(),
+        };
+
+        let health = self
+            .health
+            .as_ref()
+            .map_or("null".into(), |h| utils::json_string(&h.to_string()));
+
+        let package = match &self.package {
+            Some(Some(pkg)) => format!(
+                "{{\"name\":{},\"version\":{}}}",
+                utils::json_string(&pkg.name),
+                utils::json_string(pkg.version())
+            ),
+            _ => "null".to_string(),
+        };
+
+        // only with `--all-scopes`
+        let scope = self
+            .scope
+            .as_ref()
+            .map(|s| format!(",\"scope\":{}", utils::json_string(s)))
+            .unwrap_or_default();
+
+        format!(
+            "{{\"name\":{},\"state\":{},\"enabled\":{},\"pid\":{},\"command\":{},\"uptime\":{},\"want\":{},\"paused\":{},\"log\":{},\"health\":{},\"package\":{}{}}}",
+            utils::json_string(&self.name),
+            utils::json_string(&self.state.to_string()),
+            self.enabled,
+            opt_num(self.pid.map(|pid| pid as u64)),
+            self.command.as_deref().map_or("null".into(), utils::json_string),
+            opt_num(uptime),
+            match self.want {
+                'u' | 'd' => utils::json_string(&self.want.to_string()),
+                _ => "null".to_string(),
+            },
+            self.paused,
+            log,
+            health,
+            package,
+            scope
+        )
+    }
+
+    fn format_health(&self) -> Option<(String, Style)> {
+        self.health.as_ref().map(|health| (health.to_string(), health.style()))
+    }
+
     fn format_name(&self) -> (String, Style) {
         (self.name.to_string(), Style::default())
     }
@@ -171,6 +308,7 @@ impl Service {
             }
             ServiceState::Down => ("X".to_string(), style.fg(Color::Red)),
             ServiceState::Finish => ("X".to_string(), style.fg(Color::Red)),
+            ServiceState::Broken => ("!".to_string(), style.fg(Color::Red)),
             ServiceState::Unknown => ("?".to_string(), style.fg(Color::Yellow)),
         }
     }
@@ -183,6 +321,9 @@ impl Service {
             ServiceState::Finish => {
                 ("finish".to_string(), style.fg(Color::Yellow))
             }
+            ServiceState::Broken => {
+                ("broken".to_string(), style.fg(Color::Red))
+            }
             ServiceState::Unknown => {
                 ("n/a".to_string(), style.fg(Color::Yellow))
             }
@@ -190,6 +331,10 @@ impl Service {
     }
 
     fn format_enabled(&self) -> (String, Style) {
+        if self.state == ServiceState::Broken {
+            return ("---".to_string(), Style::default().dim());
+        }
+
         let style = match self.enabled {
             true => Style::default().fg(Color::Green),
             false => Style::default().fg(Color::Red),
@@ -277,6 +422,7 @@ impl fmt::Display for Service {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let base = utils::format_status_line(
             self.format_status_char(),
+            self.format_scope(),
             self.format_name(),
             self.format_state(),
             self.format_enabled(),
@@ -284,6 +430,8 @@ impl fmt::Display for Service {
             self.format_command(),
             self.format_time(),
             self.format_log(),
+            self.format_health(),
+            self.format_package(),
         );
 
         base.fmt(f)
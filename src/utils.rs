@@ -8,19 +8,27 @@
 
 use libc::pid_t;
 use std::collections::{HashMap, HashSet};
-use std::ffi::OsStr;
+use std::env;
+use std::ffi::{CStr, CString, OsStr};
 use std::fs;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use yansi::{Paint, Style};
 
 use crate::config;
 
+/// Time to sleep between checks in `wait_until`.
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
 /**
  * A `println!()`-like macro that will only print if `-v` is set.
  */
@@ -37,8 +45,10 @@ pub(crate) use verbose;
 /**
  * Format a status line - made specifically for vsv.
  */
+#[allow(clippy::too_many_arguments)]
 pub fn format_status_line<T: AsRef<str>>(
     status_char: (T, Style),
+    scope: Option<(T, Style)>,
     name: (T, Style),
     state: (T, Style),
     enabled: (T, Style),
@@ -46,11 +56,19 @@ pub fn format_status_line<T: AsRef<str>>(
     command: (T, Style),
     time: (T, Style),
     log: (T, Style),
+    health: Option<(T, Style)>,
+    package: Option<(T, Style)>,
 ) -> String {
     // ( data + style to print, max width, suffix )
     // We add a "  " suffix to enforce a gap between columns.
-    let data = [
-        (status_char.0.as_ref(), status_char.1, 1, "  "),
+    let mut data = vec![(status_char.0.as_ref(), status_char.1, 1, "  ")];
+
+    // the optional scope column goes before the service name
+    if let Some(scope) = &scope {
+        data.push((scope.0.as_ref(), scope.1, 12, "  "));
+    }
+
+    data.extend([
         (name.0.as_ref(), name.1, 20, "  "),
         (state.0.as_ref(), state.1, 7, "  "),
         (enabled.0.as_ref(), enabled.1, 9, "  "),
@@ -58,20 +76,34 @@ pub fn format_status_line<T: AsRef<str>>(
         (command.0.as_ref(), command.1, 17, "  "),
         (time.0.as_ref(), time.1, 9, "  "),
         (log.0.as_ref(), log.1, 7, ""), // Last column has no suffix
-    ];
+    ]);
+
+    // the optional health and package columns go last
+    if let Some(health) = &health {
+        data.last_mut().expect("columns").3 = "  ";
+        data.push((health.0.as_ref(), health.1, 9, ""));
+    }
+    if let Some(package) = &package {
+        data.last_mut().expect("columns").3 = "  ";
+        data.push((package.0.as_ref(), package.1, 24, ""));
+    }
 
     let mut line = String::new();
 
-    for (_i, (s, style, width, suffix)) in data.iter().enumerate() {
+    for (s, style, width, suffix) in data.iter() {
         let mut s = s.to_string();
         let char_count = s.chars().count();
 
-        // truncate long strings safely (by character count, not bytes)
+        // truncate long strings safely (by character count, not bytes) and
+        // mark them with "..."
         if char_count > *width {
-             // Find the byte index where the *width*-th character starts
-             if let Some((idx, _)) = s.char_indices().nth(*width) {
-                 s.truncate(idx);
-             }
+            // Find the byte index where the (*width - 3)-th character starts
+            if let Some((idx, _)) =
+                s.char_indices().nth(width.saturating_sub(3))
+            {
+                s.truncate(idx);
+                s.push_str("...");
+            }
         }
 
         // Recalculate char_count after truncation for padding logic
@@ -82,14 +114,10 @@ pub fn format_status_line<T: AsRef<str>>(
 
         // calculate the padding safely
         // We want 'width' visual columns.
-        let padding = if *width > char_count {
-            *width - char_count
-        } else {
-            0
-        };
+        let padding = width.saturating_sub(char_count);
 
         // Left Align: String first, then Padding
-        // This ensures headers ("SERVICE") and values ("NetworkManager") start 
+        // This ensures headers ("SERVICE") and values ("NetworkManager") start
         // at the same column.
         line.push_str(&s_painted);
         let pad_str = " ".repeat(padding);
@@ -139,6 +167,331 @@ pub fn relative_duration(t: &Duration) -> String {
     s
 }
 
+/**
+ * Format a time as an RFC 3339 UTC timestamp like "2022-02-15T18:30:00Z".
+ */
+pub fn format_timestamp(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (hour, min, sec) = (rem / 3600, rem % 3600 / 60, rem % 60);
+
+    // convert days since the epoch to a civil (y/m/d) date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, min, sec
+    )
+}
+
+/// Get the user name for a given uid from the password database.
+pub fn get_username(uid: libc::uid_t) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    let ret = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut pwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/**
+ * Write a file atomically (write a temp file in the same directory and rename
+ * it into place) with the given mode (subject to the umask).
+ */
+pub fn write_atomic(path: &Path, content: &[u8], mode: u32) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .with_context(|| format!("invalid file name {:?}", path))?;
+    let tmp = path.with_file_name(format!(".{}.vsv-tmp", file_name));
+
+    // a temp file left behind by an earlier crash would make create_new fail
+    let _ = fs::remove_file(&tmp);
+
+    let ret = (|| -> Result<()> {
+        // created with its final mode so the content is never exposed
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&tmp)?;
+        f.write_all(content)?;
+        f.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+
+    if ret.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    ret.with_context(|| format!("failed to write {:?}", path))
+}
+
+/**
+ * Get the path to `path` (an absolute path as seen from inside `root`) on the
+ * host, e.g. `/etc/sv` in `/mnt/img` is `/mnt/img/etc/sv`.
+ */
+pub fn in_root(root: &Path, path: &Path) -> PathBuf {
+    root.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/**
+ * Resolve a path under `root` like `fs::canonicalize`, except that absolute
+ * symlink targets are resolved relative to `root` (like they would be from
+ * inside a chroot) instead of the host.  `root` itself is used as is.
+ */
+pub fn canonicalize_in_root(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    // maximum number of symlinks followed, like the kernel
+    const MAX_LINKS: usize = 40;
+
+    let root = root.to_path_buf();
+    let relative = path.strip_prefix(&root).unwrap_or(path);
+
+    // components left to resolve (in reverse order)
+    let mut pending: Vec<PathBuf> = relative
+        .components()
+        .rev()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .collect();
+    let mut resolved = root.clone();
+    let mut links = 0;
+
+    while let Some(component) = pending.pop() {
+        match component.components().next() {
+            None | Some(Component::RootDir) | Some(Component::CurDir) => {}
+            Some(Component::ParentDir) => {
+                if resolved != root {
+                    resolved.pop();
+                }
+            }
+            Some(_) => {
+                let next = resolved.join(&component);
+                if !fs::symlink_metadata(&next)?.file_type().is_symlink() {
+                    resolved = next;
+                    continue;
+                }
+
+                links += 1;
+                if links > MAX_LINKS {
+                    return Err(io::Error::other(format!(
+                        "too many levels of symbolic links: {:?}",
+                        path
+                    )));
+                }
+
+                let target = fs::read_link(&next)?;
+                if target.is_absolute() {
+                    resolved = root.clone();
+                }
+                pending.extend(
+                    target
+                        .components()
+                        .rev()
+                        .map(|c| PathBuf::from(c.as_os_str())),
+                );
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Check if the current user can write to a path (see access(2)).
+pub fn is_writable(path: &Path) -> bool {
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+/// Create a private (mode 0700) temporary directory with mkdtemp(3).
+pub fn make_temp_dir(prefix: &str) -> Result<PathBuf> {
+    let template = env::temp_dir().join(format!("{}.XXXXXX", prefix));
+    let mut buf =
+        CString::new(template.as_os_str().as_bytes())?.into_bytes_with_nul();
+
+    let ret = unsafe { libc::mkdtemp(buf.as_mut_ptr().cast()) };
+    if ret.is_null() {
+        return Err(io::Error::last_os_error()).with_context(|| {
+            format!("failed to create temp dir {:?}", template)
+        });
+    }
+
+    buf.pop();
+    Ok(PathBuf::from(OsStr::from_bytes(&buf)))
+}
+
+/**
+ * Get a relative path from the directory `from` to `to`, e.g. from
+ * `/etc/runit/runsvdir/default` to `/etc/sv/sshd` is `../../../sv/sshd`.
+ * Both paths should be absolute (and resolved).
+ */
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+
+    path
+}
+
+/**
+ * Open a file in the user's editor (`$VISUAL`, `$EDITOR`, or `vi`).
+ */
+pub fn run_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // run through the shell so editors with arguments (e.g. "code -w") work
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to run editor {:?}", editor))?;
+
+    ensure!(status.success(), "editor {:?} exited with {}", editor, status);
+
+    Ok(())
+}
+
+/// Quote and escape a string for JSON output.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Check if a string is a valid shell variable name.
+pub fn valid_var_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/**
+ * Ask the user a yes/no question on stdin (anything but "y" or "yes" is no).
+ */
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/**
+ * Poll `f` until it returns true or `timeout` passes, returning whether it
+ * succeeded.
+ */
+pub fn wait_until<F: FnMut() -> bool>(timeout: Duration, mut f: F) -> bool {
+    let started = Instant::now();
+    loop {
+        if f() {
+            return true;
+        }
+
+        if started.elapsed() >= timeout {
+            return false;
+        }
+
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+/**
+ * Read a `chpst -e` style environment directory.
+ *
+ * Each file is a variable: the value is the first line of the file (with
+ * trailing whitespace removed and NUL bytes turned into newlines), and an
+ * empty file means the variable should be removed (`None`).
+ */
+pub fn read_envdir(dir: &Path) -> Result<Vec<(String, Option<String>)>> {
+    let mut vars = vec![];
+
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to read dir {:?}", dir))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || name.contains('=') {
+            continue;
+        }
+
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let content = fs::read(&path)
+            .with_context(|| format!("failed to read {:?}", path))?;
+
+        let value = if content.is_empty() {
+            None
+        } else {
+            let content = String::from_utf8_lossy(&content);
+            let line = content.lines().next().unwrap_or("");
+            Some(line.trim_end_matches([' ', '\t']).replace('\0', "\n"))
+        };
+
+        vars.push((name, value));
+    }
+
+    vars.sort();
+
+    Ok(vars)
+}
+
 /// Get the command line for a PID from /proc
 pub fn get_command_from_pid(pid: pid_t, proc_path: &Path) -> Result<String> {
     let path = proc_path.join(pid.to_string()).join("cmdline");
@@ -150,13 +503,63 @@ pub fn get_command_from_pid(pid: pid_t, proc_path: &Path) -> Result<String> {
     Ok(cmd.trim().to_string())
 }
 
+/// Check if a process exists in /proc.
+pub fn pid_exists(pid: pid_t, proc_path: &Path) -> bool {
+    proc_path.join(pid.to_string()).exists()
+}
+
+/**
+ * Find the `runsv` process(es) supervising the service `name`.
+ *
+ * runsvdir runs `runsv <name>`, which then changes into the service
+ * directory, so a process matches if its cmdline is exactly that and (when it
+ * can be read) its cwd is `service_dir`.
+ */
+pub fn find_runsv_pids(
+    name: &str,
+    service_dir: &Path,
+    proc_path: &Path,
+) -> Vec<pid_t> {
+    let want_cmdline = format!("runsv\0{}\0", name);
+    let service_dir = fs::canonicalize(service_dir)
+        .unwrap_or_else(|_| service_dir.to_path_buf());
+
+    let Ok(entries) = fs::read_dir(proc_path) else {
+        return vec![];
+    };
+
+    let mut pids = vec![];
+    for entry in entries.flatten() {
+        let Some(pid) =
+            entry.file_name().to_str().and_then(|s| s.parse::<pid_t>().ok())
+        else {
+            continue;
+        };
+
+        let path = entry.path();
+        match fs::read_to_string(path.join("cmdline")) {
+            Ok(cmdline) if cmdline == want_cmdline => {}
+            _ => continue,
+        }
+
+        if let Ok(cwd) = fs::canonicalize(path.join("cwd")) {
+            if cwd != service_dir {
+                continue;
+            }
+        }
+
+        pids.push(pid);
+    }
+
+    pids.sort();
+
+    pids
+}
+
 /// Helper struct to hold process information
 #[derive(Debug, Clone)]
 struct ProcNode {
-    pid: pid_t,
-    ppid: pid_t,
     name: String,
-    is_thread: bool,
 }
 
 /// Generate a process tree string for a given PID by reading /proc manually.
@@ -215,10 +618,7 @@ pub fn get_pstree(root_pid: pid_t, proc_path: &Path) -> Result<String> {
             format!("{}", pid)
         };
 
-        procs.insert(
-            pid,
-            ProcNode { pid, ppid, name: name.clone(), is_thread: false },
-        );
+        procs.insert(pid, ProcNode { name });
         children_map.entry(ppid).or_default().push(pid);
 
         let task_path = path.join("task");
@@ -244,15 +644,7 @@ pub fn get_pstree(root_pid: pid_t, proc_path: &Path) -> Result<String> {
                         let t_comm = t_stat[l + 1..r].to_string();
                         let t_name = format!("{{{}}}", t_comm);
 
-                        procs.insert(
-                            tid,
-                            ProcNode {
-                                pid: tid,
-                                ppid: pid,
-                                name: t_name,
-                                is_thread: true,
-                            },
-                        );
+                        procs.insert(tid, ProcNode { name: t_name });
                         children_map.entry(pid).or_default().push(tid);
                     }
                 }
@@ -342,13 +734,8 @@ fn get_tail_content(
     let initial_read_size = std::cmp::max(8192, estimated_bytes);
 
     // If reading all, start at 0. Else, try to be smart.
-    let start_pos = if read_all {
-        0
-    } else if file_len > initial_read_size {
-        file_len - initial_read_size
-    } else {
-        0
-    };
+    let start_pos =
+        if read_all { 0 } else { file_len.saturating_sub(initial_read_size) };
 
     file.seek(SeekFrom::Start(start_pos))?;
     let mut buf = Vec::new();
@@ -362,11 +749,7 @@ fn get_tail_content(
  *
  * This function will run forever until interrupted.
  */
-pub fn follow_file(
-    path: &Path,
-    n_lines: usize,
-    read_all: bool,
-) -> Result<()> {
+pub fn follow_file(path: &Path, n_lines: usize, read_all: bool) -> Result<()> {
     follow_file_filtered(path, "", n_lines, read_all)
 }
 
@@ -428,7 +811,7 @@ pub fn follow_file_filtered(
                     pos = 0;
                     file = File::open(path)?;
                     file.seek(SeekFrom::Start(0))?;
-                    partial_line.truncate(0); 
+                    partial_line.truncate(0);
                     println!("\n*** Log truncated ***\n");
                 }
             }
@@ -459,7 +842,7 @@ pub fn get_service_names(dir: &Path) -> Vec<String> {
             }
         }
     }
-    
+
     names.sort();
     names
 }
@@ -472,16 +855,25 @@ pub fn get_running_services() -> Vec<String> {
     let svdir = std::env::var_os(config::ENV_SVDIR)
         .map(PathBuf::from)
         .unwrap_or_else(|| PathBuf::from(config::DEFAULT_SVDIR));
-    
+
     get_service_names(&svdir)
 }
 
 /**
- * Get available services from /etc/sv (or default avail dir).
+ * Get available services from the avail dirs (`SVAVAIL`, config file, or
+ * /etc/sv).
  */
 pub fn get_avail_services() -> Vec<String> {
-    // Note: We don't have a specific env var for AVAIL_DIR in standard vsv,
-    // but config.rs defines it. We'll use the constant default here for completion.
-    let avail_dir = PathBuf::from(config::DEFAULT_AVAIL_DIR);
-    get_service_names(&avail_dir)
+    let config_file = config::ConfigFile::load(
+        &config::get_config_dir().join(config::CONFIG_FILE_NAME),
+    )
+    .unwrap_or_default();
+    let avail_dirs =
+        config::get_avail_dirs(None, false, &config_file).unwrap_or_default();
+
+    let mut names: Vec<String> =
+        avail_dirs.iter().flat_map(|dir| get_service_names(dir)).collect();
+    names.sort();
+    names.dedup();
+    names
 }
@@ -0,0 +1,329 @@
+/*
+ * Author: Dave Eddy <dave@daveeddy.com>
+ * Date: October 18, 2026
+ * License: MIT
+ */
+
+//! Read-only access to the local xbps package database.
+//!
+//! xbps keeps the metadata of every installed package in
+//! `<dbdir>/pkgdb-0.38.plist`, and the list of files installed by each package
+//! in `<dbdir>/.<pkgname>-files.plist` (both XML property lists), which is
+//! enough to find out which package a service came from.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::utils;
+
+/// Name of the package database file in the database dir.
+pub const PKGDB_FILE: &str = "pkgdb-0.38.plist";
+
+/// An installed package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /// Name and version, like `openssh-9.8p1_1`.
+    pub pkgver: String,
+    pub short_desc: String,
+}
+
+impl Package {
+    /// The version (and revision) of the package, like `9.8p1_1`.
+    pub fn version(&self) -> &str {
+        self.pkgver
+            .strip_prefix(&self.name)
+            .and_then(|s| s.strip_prefix('-'))
+            .unwrap_or(&self.pkgver)
+    }
+}
+
+/// A file (or symlink) installed by a package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageFile {
+    pub path: PathBuf,
+    pub sha256: Option<String>,
+    /// Where the symlink points (for links).
+    pub target: Option<String>,
+}
+
+/// The installed packages, and which package owns each service file.
+#[derive(Debug, Default)]
+pub struct PkgDb {
+    packages: HashMap<String, Package>,
+    files: HashMap<String, Vec<PackageFile>>,
+    owners: HashMap<PathBuf, String>,
+}
+
+impl PkgDb {
+    /**
+     * Load the package database (a missing or unreadable database is empty).
+     *
+     * Only the files plists of packages that look like they ship runit
+     * services (have a file named `run`) are parsed.  The paths of the files
+     * are prefixed with `root` (see `--root`) if given.
+     */
+    pub fn load(dbdir: &Path, root: Option<&Path>) -> Self {
+        let mut db = Self::default();
+
+        let pkgdb = fs::read_to_string(dbdir.join(PKGDB_FILE))
+            .ok()
+            .and_then(|s| parse_plist(&s).ok());
+        if let Some(Plist::Dict(entries)) = pkgdb {
+            for (name, pkg) in entries {
+                let field = |key| {
+                    pkg.get(key).and_then(Plist::as_str).unwrap_or_default()
+                };
+                let package = Package {
+                    name: name.clone(),
+                    pkgver: field("pkgver").to_string(),
+                    short_desc: field("short_desc").to_string(),
+                };
+                db.packages.insert(name, package);
+            }
+        }
+
+        let Ok(entries) = fs::read_dir(dbdir) else {
+            return db;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(pkgname) = name.to_str().and_then(files_plist_pkgname)
+            else {
+                continue;
+            };
+
+            let Ok(content) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            if !content.contains("/run</string>") {
+                continue;
+            }
+            let Ok(plist) = parse_plist(&content) else {
+                continue;
+            };
+
+            let files: Vec<PackageFile> = package_files(&plist)
+                .map(|mut file| {
+                    if let Some(root) = root {
+                        file.path = utils::in_root(root, &file.path);
+                    }
+                    file
+                })
+                .collect();
+            for file in &files {
+                db.owners.insert(file.path.clone(), pkgname.to_string());
+            }
+            db.files.insert(pkgname.to_string(), files);
+        }
+
+        db
+    }
+
+    /// Get the package that owns a file.
+    pub fn owner(&self, file: &Path) -> Option<&Package> {
+        let name = self.owners.get(file)?;
+        self.packages.get(name)
+    }
+
+    /// Get the package that ships a service (owns its `run` script).
+    pub fn service_owner(&self, dir: &Path) -> Option<&Package> {
+        self.owner(&dir.join("run"))
+    }
+
+    /// Get the files installed by a package under a directory.
+    pub fn files_in(&self, pkgname: &str, dir: &Path) -> Vec<&PackageFile> {
+        self.files
+            .get(pkgname)
+            .map(|files| files.iter().filter(|f| f.path.starts_with(dir)))
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+/// Get the package name from a files plist file name
+/// (`.<pkgname>-files.plist`).
+fn files_plist_pkgname(file_name: &str) -> Option<&str> {
+    file_name.strip_prefix('.')?.strip_suffix("-files.plist")
+}
+
+/// A (subset of an) XML property list value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Plist {
+    Dict(Vec<(String, Plist)>),
+    Array(Vec<Plist>),
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    /// Values we don't care about (`data`, `date`, `real`).
+    Other(String),
+}
+
+impl Plist {
+    /// Get a value from a dictionary by key.
+    pub fn get(&self, key: &str) -> Option<&Plist> {
+        match self {
+            Plist::Dict(entries) => {
+                entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the value as a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Plist::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an array.
+    pub fn as_array(&self) -> Option<&[Plist]> {
+        match self {
+            Plist::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse an XML property list document.
+pub fn parse_plist(s: &str) -> Result<Plist> {
+    let mut parser = Parser { s, pos: 0 };
+
+    loop {
+        let tag = parser.next_tag()?.ok_or_else(|| anyhow!("no <plist>"))?;
+        if tag.starts_with("plist") {
+            break;
+        }
+    }
+
+    let tag = parser.next_tag()?.ok_or_else(|| anyhow!("empty <plist>"))?;
+    parser.value(&tag)
+}
+
+/// Iterate every file, config file, and link in a files plist.
+pub fn package_files(plist: &Plist) -> impl Iterator<Item = PackageFile> + '_ {
+    ["files", "conf_files", "links"]
+        .into_iter()
+        .filter_map(|key| plist.get(key).and_then(Plist::as_array))
+        .flatten()
+        .filter_map(|entry| {
+            let field =
+                |key| entry.get(key).and_then(Plist::as_str).map(String::from);
+            Some(PackageFile {
+                path: PathBuf::from(entry.get("file")?.as_str()?),
+                sha256: field("sha256"),
+                target: field("target"),
+            })
+        })
+}
+
+/// A minimal pull parser for the XML in property lists.
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /**
+     * Get the next tag (without `<` and `>`), skipping text, the XML
+     * declaration, comments, and the doctype.
+     */
+    fn next_tag(&mut self) -> Result<Option<String>> {
+        loop {
+            let Some(start) = self.s[self.pos..].find('<') else {
+                return Ok(None);
+            };
+            let start = self.pos + start;
+
+            let rest = &self.s[start..];
+            let end_marker = if rest.starts_with("<!--") { "-->" } else { ">" };
+            let end = rest
+                .find(end_marker)
+                .ok_or_else(|| anyhow!("unterminated tag"))?;
+            self.pos = start + end + end_marker.len();
+
+            let tag = &rest[1..end];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+
+            return Ok(Some(tag.trim().to_string()));
+        }
+    }
+
+    /// Get the text up to the closing tag `</name>`.
+    fn text(&mut self, name: &str) -> Result<String> {
+        let close = format!("</{}>", name);
+        let end = self.s[self.pos..]
+            .find(&close)
+            .ok_or_else(|| anyhow!("missing {}", close))?;
+        let text = unescape(&self.s[self.pos..self.pos + end]);
+        self.pos += end + close.len();
+
+        Ok(text)
+    }
+
+    /// Parse the value that starts with the given (already read) tag.
+    fn value(&mut self, tag: &str) -> Result<Plist> {
+        let value = match tag {
+            "dict/" => Plist::Dict(vec![]),
+            "array/" => Plist::Array(vec![]),
+            "string/" => Plist::String(String::new()),
+            "true/" => Plist::Bool(true),
+            "false/" => Plist::Bool(false),
+            "dict" => {
+                let mut entries = vec![];
+                loop {
+                    match self.next_tag()?.as_deref() {
+                        Some("/dict") => break,
+                        Some("key") => {
+                            let key = self.text("key")?;
+                            let tag = self
+                                .next_tag()?
+                                .ok_or_else(|| anyhow!("missing value"))?;
+                            entries.push((key, self.value(&tag)?));
+                        }
+                        Some(tag) => bail!("unexpected <{}> in <dict>", tag),
+                        None => bail!("unterminated <dict>"),
+                    }
+                }
+                Plist::Dict(entries)
+            }
+            "array" => {
+                let mut items = vec![];
+                loop {
+                    match self.next_tag()? {
+                        Some(tag) if tag == "/array" => break,
+                        Some(tag) => items.push(self.value(&tag)?),
+                        None => bail!("unterminated <array>"),
+                    }
+                }
+                Plist::Array(items)
+            }
+            "string" => Plist::String(self.text("string")?),
+            "integer" => {
+                let text = self.text("integer")?;
+                Plist::Integer(text.trim().parse()?)
+            }
+            "data" | "date" | "real" => Plist::Other(self.text(tag)?),
+            tag => bail!("unexpected <{}>", tag),
+        };
+
+        Ok(value)
+    }
+}
+
+/// Decode the XML entities used in plists.
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -5,10 +5,11 @@
  */
 
 use anyhow::Result;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_cmd::Command;
 
 pub fn vsv() -> Result<Command> {
-    let mut cmd = Command::cargo_bin("vsv")?;
+    let mut cmd = cargo_bin_cmd!("vsv");
 
     cmd.env_clear();
 
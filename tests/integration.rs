@@ -9,8 +9,10 @@
 use std::fs;
 use std::fs::File;
 use std::io::Write;
+use std::os::unix::fs::{symlink, PermissionsExt};
 use std::path::{Path, PathBuf};
 use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Result};
 use assert_cmd::Command;
@@ -27,6 +29,13 @@ fn vsv(cfg: &Config) -> Result<Command> {
 
     cmd.env("SVDIR", &cfg.service_path);
     cmd.env("PROC_DIR", &cfg.proc_path);
+    cmd.env("VSV_AUDIT_LOG", cfg.service_path.with_file_name("audit.log"));
+    cmd.env("VSV_JOURNAL", cfg.service_path.with_file_name("journal"));
+    cmd.env("VSV_HEALTH_CACHE", cfg.service_path.with_file_name("health"));
+    cmd.env("VSV_CONFIG_DIR", cfg.service_path.with_file_name("config"));
+    cmd.env("VSV_XBPS_DBDIR", cfg.service_path.with_file_name("xbps"));
+    cmd.env("VSV_RUNLEVEL_DIR", cfg.service_path.with_file_name("runsvdir"));
+    cmd.env_remove("SVAVAIL");
 
     Ok(cmd)
 }
@@ -46,12 +55,10 @@ fn parse_status_line(line: &str) -> Result<Vec<&str>> {
     let mut vec: Vec<&str> = vec![];
     let mut chars = line.chars().map(|c| c.len_utf8());
 
-    let lengths = [1, 20, 7, 9, 8, 17];
+    // every column is followed by a 2 space gap
+    let lengths = [1, 20, 7, 9, 8, 17, 9];
 
-    // skip the first space char
     let mut start = 0;
-    start += chars.next().ok_or(anyhow!("first char must be a space"))?;
-    assert_eq!(start, 1, "first char should always be 1 byte (space)");
 
     for num in lengths {
         let mut end = start;
@@ -62,13 +69,16 @@ fn parse_status_line(line: &str) -> Result<Vec<&str>> {
 
         vec.push(&line[start..end]);
 
-        let space = chars
-            .next()
-            .ok_or(anyhow!("next field should have a space char"))?;
-
-        assert_eq!(space, 1, "should be space character");
+        let mut gap = 0;
+        for _ in 0..2 {
+            let space = chars
+                .next()
+                .ok_or(anyhow!("next field should have a space char"))?;
+            assert_eq!(space, 1, "should be space character");
+            gap += space;
+        }
 
-        start = end + space;
+        start = end + gap;
     }
 
     vec.push(&line[start..]);
@@ -110,6 +120,68 @@ fn parse_status_output(s: &str) -> Result<Vec<Vec<&str>>> {
     Ok(lines)
 }
 
+/// Build a runit `supervise/status` (binary) file for a given state and pid.
+fn make_status(state: &str, pid: Option<&str>) -> Vec<u8> {
+    let mut buf = vec![0u8; 20];
+
+    // tai64n timestamp (1 minute ago)
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let tai = 4611686018427387914u64 + now - 60;
+    buf[0..8].copy_from_slice(&tai.to_be_bytes());
+
+    let pid: u32 = pid.and_then(|p| p.parse().ok()).unwrap_or(0);
+    buf[12..16].copy_from_slice(&pid.to_le_bytes());
+
+    buf[17] = b'u';
+    buf[19] = match state {
+        "down" => 0,
+        "run" => 1,
+        "finish" => 2,
+        _ => 9,
+    };
+
+    buf
+}
+
+/// A fixture package: `(pkgname, pkgver, short_desc, [(file, sha256)])`.
+type FixturePackage<'a> =
+    (&'a str, &'a str, &'a str, &'a [(&'a Path, &'a str)]);
+
+/// Write a fixture xbps database.
+fn write_xbps_db(cfg: &Config, packages: &[FixturePackage]) -> Result<()> {
+    let dbdir = cfg.service_path.with_file_name("xbps");
+    fs::create_dir_all(&dbdir)?;
+
+    let header = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+"#;
+
+    let mut pkgdb = format!("{}<dict>\n", header);
+    for (name, pkgver, desc, files) in packages {
+        pkgdb.push_str(&format!(
+            "\t<key>{}</key>\n\t<dict>\n\t\t<key>pkgver</key>\n\t\t<string>{}</string>\n\t\t<key>short_desc</key>\n\t\t<string>{}</string>\n\t\t<key>state</key>\n\t\t<string>installed</string>\n\t</dict>\n",
+            name, pkgver, desc
+        ));
+
+        let mut plist =
+            format!("{}<dict>\n\t<key>files</key>\n\t<array>\n", header);
+        for (path, sha256) in *files {
+            plist.push_str(&format!(
+                "\t\t<dict>\n\t\t\t<key>file</key>\n\t\t\t<string>{}</string>\n\t\t\t<key>sha256</key>\n\t\t\t<string>{}</string>\n\t\t</dict>\n",
+                path.display(),
+                sha256
+            ));
+        }
+        plist.push_str("\t</array>\n</dict>\n</plist>\n");
+        write_file(&dbdir.join(format!(".{}-files.plist", name)), &plist)?;
+    }
+    pkgdb.push_str("</dict>\n</plist>\n");
+    write_file(&dbdir.join("pkgdb-0.38.plist"), &pkgdb)?;
+
+    Ok(())
+}
+
 fn create_service(
     cfg: &Config,
     name: &str,
@@ -122,20 +194,22 @@ fn create_service(
 
     for (s, dir, pid) in dirs {
         let supervise_dir = dir.join("supervise");
-        let stat_file = supervise_dir.join("stat");
+        let status_file = supervise_dir.join("status");
 
-        fs::create_dir(&dir)?;
+        fs::create_dir(dir)?;
         fs::create_dir(&supervise_dir)?;
-        fs::write(&stat_file, format!("{}\n", state))?;
+        fs::write(&status_file, make_status(state, pid))?;
 
-        // write pid and proc info if supplied
+        // write proc info if supplied
         if let Some(pid) = pid {
             let proc_pid_dir = cfg.proc_path.join(pid);
-            let pid_file = supervise_dir.join("pid");
             let cmd_file = proc_pid_dir.join("cmdline");
 
+            if pid.parse::<u32>().is_err() {
+                continue;
+            }
+
             fs::create_dir(&proc_pid_dir)?;
-            fs::write(&pid_file, format!("{}\n", pid))?;
             fs::write(&cmd_file, format!("{}-{}\0", name, s))?;
         }
     }
@@ -328,7 +402,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // test disable
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["disable", "bar", "baz"]).assert().success();
+    cmd.args(["disable", "bar", "baz"]).assert().success();
 
     let want = &[
         &["✔", "bar", "run", "false", "234", "bar-cmd"],
@@ -340,7 +414,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // test enable
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["enable", "foo", "bar"]).assert().success();
+    cmd.args(["enable", "foo", "bar"]).assert().success();
     let want = &[
         &["✔", "bar", "run", "true", "234", "bar-cmd"],
         &["✔", "bat", "run", "true", "456", "bat-cmd"],
@@ -351,7 +425,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // test bad disable
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["disable", "fake-service", "foo"]).assert().failure();
+    cmd.args(["disable", "fake-service", "foo"]).assert().failure();
     let want = &[
         &["✔", "bar", "run", "true", "234", "bar-cmd"],
         &["✔", "bat", "run", "true", "456", "bat-cmd"],
@@ -362,7 +436,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // test bad enable
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["enable", "fake-service", "foo"]).assert().failure();
+    cmd.args(["enable", "fake-service", "foo"]).assert().failure();
     let want = &[
         &["✔", "bar", "run", "true", "234", "bar-cmd"],
         &["✔", "bat", "run", "true", "456", "bat-cmd"],
@@ -389,7 +463,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // disable logger only
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["disable", "foo/log"]).assert().success();
+    cmd.args(["disable", "foo/log"]).assert().success();
     let want = &[
         &["✔", "foo", "run", "true", "100", "foo-cmd"],
         &["✔", "- log", "run", "false", "150", "foo-log"],
@@ -416,7 +490,7 @@ fn full_synthetic_test() -> Result<()> {
     create_service(&cfg, "test-2", "run", Some("2"), None)?;
     create_service(&cfg, "test-3", "run", Some("3"), None)?;
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["status", "test"]).assert().success();
+    cmd.args(["status", "test"]).assert().success();
     let want = &[
         &["✔", "test-1", "run", "true", "1", "test-1-cmd"],
         &["✔", "test-2", "run", "true", "2", "test-2-cmd"],
@@ -426,7 +500,7 @@ fn full_synthetic_test() -> Result<()> {
 
     // status mode should work without "status" when -t or -l is supplied
     let mut cmd = vsv(&cfg)?;
-    cmd.args(&["-l", "test"]).assert().success();
+    cmd.args(["-l", "test"]).assert().success();
     let want = &[
         &["✔", "test-1", "run", "true", "1", "test-1-cmd"],
         &["✔", "- log", "run", "true", "---", "---"],
@@ -439,3 +513,1912 @@ fn full_synthetic_test() -> Result<()> {
 
     Ok(())
 }
+
+fn setup_tmp_config(name: &str) -> Result<Config> {
+    let tmp_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+
+    let cfg = Config {
+        proc_path: tmp_path.join("proc"),
+        service_path: tmp_path.join("service"),
+    };
+
+    // start fresh by removing the service and proc paths
+    let _ = fs::remove_dir_all(&tmp_path);
+    for p in [&tmp_path, &cfg.proc_path, &cfg.service_path] {
+        fs::create_dir(p)?;
+    }
+
+    Ok(cfg)
+}
+
+#[test]
+fn audit_log_test() -> Result<()> {
+    let cfg = setup_tmp_config("audit")?;
+    let audit_log = cfg.service_path.with_file_name("audit.log");
+
+    // supervise/control is just a file here, we only care about the audit log
+    create_service(&cfg, "foo", "run", Some("123"), None)?;
+    create_service(&cfg, "bar", "down", None, None)?;
+    for name in ["foo", "bar"] {
+        let control = cfg.service_path.join(name).join("supervise/control");
+        write_file(&control, "")?;
+    }
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["stop", "foo"]).assert().success();
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SUDO_USER", "dave").args(["disable", "bar"]).assert().success();
+
+    let content = fs::read_to_string(&audit_log)?;
+    let lines: Vec<Vec<&str>> =
+        content.lines().map(|l| l.split('\t').collect()).collect();
+    assert_eq!(lines.len(), 2, "two audit records");
+    assert_eq!(&lines[0][2..], &["-", "stop", "foo", "run", "ok"]);
+    assert_eq!(&lines[1][2..], &["dave", "disable", "bar", "enabled", "ok"]);
+
+    // filter by service
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["audit", "foo"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("stop"));
+    assert!(!stdout.contains("disable"));
+
+    // filter by user
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["audit", "--user", "dave"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(!stdout.contains("stop"));
+    assert!(stdout.contains("disable"));
+
+    // a truncated line (e.g. from a crash mid-write) is skipped
+    let mut f = fs::OpenOptions::new().append(true).open(&audit_log)?;
+    f.write_all(b"2026-10-18T00:00:00Z\t0\t-\tsto")?;
+    drop(f);
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["-v", "audit"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stdout.contains("stop"));
+    assert!(stderr.contains("skipping invalid record"));
+
+    Ok(())
+}
+
+#[test]
+fn undo_test() -> Result<()> {
+    let cfg = setup_tmp_config("undo")?;
+
+    create_service(&cfg, "foo", "run", Some("123"), None)?;
+    let foo = cfg.service_path.join("foo");
+    let control = foo.join("supervise/control");
+    write_file(&control, "")?;
+
+    // undo a disable
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["disable", "foo"]).assert().success();
+    assert!(foo.join("down").exists());
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.arg("undo").assert().success();
+    assert!(!foo.join("down").exists());
+
+    // undo a stop (service was wanted up)
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["stop", "foo"]).assert().success();
+    assert_eq!(fs::read_to_string(&control)?, "d");
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.arg("undo").assert().success();
+    assert_eq!(fs::read_to_string(&control)?, "u");
+
+    // signals can't be undone, so nothing is recorded for them
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["hup", "foo"]).assert().success();
+
+    // undo a named transaction (the first one: disable) again
+    let journal =
+        fs::read_to_string(cfg.service_path.with_file_name("journal"))?;
+    let first = journal.lines().next().unwrap().split('\t').next().unwrap();
+    fs::File::create(foo.join("down"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["undo", first]).assert().success();
+    assert!(!foo.join("down").exists());
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["undo", "no-such-transaction"]).assert().failure();
+
+    // entries from concurrent runs can be interleaved in the journal, and a
+    // corrupt line (e.g. from a crash mid-write) is skipped
+    create_service(&cfg, "bar", "run", Some("125"), None)?;
+    let bar = cfg.service_path.join("bar");
+    fs::File::create(foo.join("down"))?;
+    fs::File::create(bar.join("down"))?;
+    let ts = "2026-10-18T00:00:00Z";
+    let journal = [
+        format!("tx-a\t{}\tenable\tfoo\t{}\tenabled\tyes", ts, foo.display()),
+        format!("tx-b\t{}\tstop\tfoo\t{}\twant\tup", ts, foo.display()),
+        format!("tx-d\t{}\tdisable\tfoo\t{}\tenab", ts, foo.display()),
+        format!("tx-a\t{}\tenable\tbar\t{}\tenabled\tyes", ts, bar.display()),
+    ];
+    write_file(
+        &cfg.service_path.with_file_name("journal"),
+        &format!("{}\n", journal.join("\n")),
+    )?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["-v", "undo", "tx-a"]).assert().success();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stderr.contains("line 3: skipping invalid entry"));
+    assert!(!foo.join("down").exists());
+    assert!(!bar.join("down").exists());
+
+    // restoring a link over something else is a conflict
+    let journal = format!(
+        "tx-c\t{}\tremove\tfoo\t{}\tlink\t/etc/sv/foo\n",
+        ts,
+        foo.display()
+    );
+    write_file(&cfg.service_path.with_file_name("journal"), &journal)?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["undo", "tx-c"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("conflict"));
+    assert!(foo.is_dir());
+
+    Ok(())
+}
+
+#[test]
+fn sv_compat_test() -> Result<()> {
+    let cfg = setup_tmp_config("sv")?;
+
+    create_service(&cfg, "foo", "run", Some("123"), Some("124"))?;
+    let foo = cfg.service_path.join("foo");
+    for dir in [foo.clone(), foo.join("log")] {
+        write_file(&dir.join("supervise/ok"), "")?;
+    }
+    write_file(&foo.join("supervise/control"), "")?;
+
+    // status (abbreviated) with the log service
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["sv", "s", "foo"]).assert().success();
+    // the fixture status is 60 seconds old (61 if a second ticked over)
+    let stdout =
+        str::from_utf8(&assert.get_output().stdout)?.replace("61s", "60s");
+    assert_eq!(stdout, "run: foo: (pid 123) 60s; run: log: (pid 124) 60s\n");
+
+    // missing services are counted in the exit code
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["sv", "status", "foo", "bar"]).assert().code(1);
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.ends_with(
+        "fail: bar: unable to change to service directory: file does not exist\n"
+    ));
+
+    // the exit code is capped at 99 (100 is for usage errors)
+    let missing: Vec<String> = (0..120).map(|i| format!("nope{}", i)).collect();
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["sv", "status"]).args(&missing).assert().code(99);
+
+    // called as `sv` with options before the command
+    let sv = cfg.service_path.with_file_name("sv");
+    symlink(assert_cmd::cargo::cargo_bin!("vsv"), &sv)?;
+    let mut cmd = Command::new(&sv);
+    cmd.env_clear().env("SVDIR", &cfg.service_path);
+    cmd.env("VSV_AUDIT_LOG", cfg.service_path.with_file_name("audit.log"));
+    cmd.env("VSV_JOURNAL", cfg.service_path.with_file_name("journal"));
+    let assert = cmd.args(["-w", "0", "restart", "foo"]).assert().code(1);
+    let stdout =
+        str::from_utf8(&assert.get_output().stdout)?.replace("61s", "60s");
+    assert_eq!(stdout, "timeout: run: foo: (pid 123) 60s\n");
+    assert_eq!(fs::read_to_string(foo.join("supervise/control"))?, "tcu");
+
+    // the check script's stdout goes to stderr
+    let check = foo.join("check");
+    write_file(&check, "#!/bin/sh\necho checked\n")?;
+    fs::set_permissions(&check, fs::Permissions::from_mode(0o755))?;
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["sv", "check", "foo"]).assert().success();
+    let output = assert.get_output();
+    assert!(str::from_utf8(&output.stdout)?.starts_with("ok: run: foo:"));
+    assert_eq!(str::from_utf8(&output.stderr)?, "checked\n");
+
+    // unknown commands are a usage error
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["sv", "bogus", "foo"]).assert().code(100);
+
+    Ok(())
+}
+
+#[test]
+fn action_test() -> Result<()> {
+    let cfg = setup_tmp_config("action")?;
+
+    create_service(&cfg, "foo", "run", Some("123"), None)?;
+    let foo = cfg.service_path.join("foo");
+    let control = foo.join("supervise/control");
+    write_file(&control, "")?;
+
+    // the action sees the service env/ dir and runs from the service dir
+    fs::create_dir(foo.join("env"))?;
+    fs::create_dir(foo.join("actions"))?;
+    write_file(&foo.join("env/GREETING"), "hello  \nignored\n")?;
+    let script = foo.join("actions/rotate");
+    write_file(&script, "#!/bin/sh\necho \"$GREETING $*\" > out\n")?;
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+    write_file(&foo.join("actions/broken"), "#!/bin/sh\n")?;
+
+    // list the actions
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["action", "foo"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("rotate"));
+    assert!(stdout.contains("broken (not executable)"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["action", "foo", "rotate", "-f", "now"]).assert().success();
+    assert_eq!(fs::read_to_string(foo.join("out"))?, "hello -f now\n");
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["action", "foo", "nope"]).assert().failure();
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["action", "foo", "../run"]).assert().failure();
+
+    // reload sends HUP without a reload action...
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["reload", "foo"]).assert().success();
+    assert_eq!(fs::read_to_string(&control)?, "h");
+
+    // ...and runs the action when there is one
+    write_file(&control, "")?;
+    let script = foo.join("actions/reload");
+    write_file(&script, "#!/bin/sh\necho reloaded > out\n")?;
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["reload", "foo"]).assert().success();
+    assert_eq!(fs::read_to_string(&control)?, "");
+    assert_eq!(fs::read_to_string(foo.join("out"))?, "reloaded\n");
+
+    Ok(())
+}
+
+#[test]
+fn health_check_test() -> Result<()> {
+    let cfg = setup_tmp_config("health")?;
+
+    let checks = [
+        ("good", "101", Some("exit 0")),
+        ("bad", "102", Some("exit 3")),
+        ("slow", "103", Some("sleep 5")),
+        ("none", "104", None),
+    ];
+    for (name, pid, check) in checks {
+        create_service(&cfg, name, "run", Some(pid), None)?;
+        if let Some(check) = check {
+            let script = cfg.service_path.join(name).join("check");
+            write_file(&script, &format!("#!/bin/sh\n{}\n", check))?;
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["check", "--timeout", "1"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "checking service bad... unhealthy (exit status: 3)",
+            "checking service good... healthy",
+            "checking service none... no check",
+            "checking service slow... unhealthy (timed out after 1s)",
+        ]
+    );
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["check", "good", "none"]).assert().success();
+
+    // status uses the cached result from the last check
+    let good = cfg.service_path.join("good/check");
+    write_file(&good, "#!/bin/sh\nexit 1\n")?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "-H", "good"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let status = parse_status_output(stdout)?;
+    assert!(stdout.lines().nth(1).unwrap().trim_end().ends_with("HEALTH"));
+    assert!(status[0][7].trim_end().ends_with("  healthy"));
+
+    // an explicit check refreshes the cache
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["check", "good"]).assert().failure();
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "--health", "good"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let status = parse_status_output(stdout)?;
+    assert!(status[0][7].trim_end().ends_with("  unhealthy"));
+
+    Ok(())
+}
+
+#[test]
+fn enable_disable_now_test() -> Result<()> {
+    let cfg = setup_tmp_config("enable-now")?;
+
+    create_service(&cfg, "foo", "run", Some("123"), None)?;
+    let foo = cfg.service_path.join("foo");
+    let control = foo.join("supervise/control");
+    write_file(&control, "")?;
+
+    // without --now only the down file changes
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["disable", "foo"]).assert().success();
+    assert!(foo.join("down").exists());
+    assert_eq!(fs::read_to_string(&control)?, "");
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["enable", "--now", "foo"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert_eq!(stdout, "enable service foo... enabled at boot, started.\n");
+    assert!(!foo.join("down").exists());
+    assert_eq!(fs::read_to_string(&control)?, "u");
+
+    // the service is already running, so waiting succeeds right away
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["enable", "--now", "--wait", "foo"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.ends_with("started (run).\n"));
+
+    // nothing is supervising the fixture, so it never goes down
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVWAIT", "0");
+    let assert = cmd.args(["disable", "--now", "-w", "foo"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("disabled at boot, failed! timed out"));
+    assert!(foo.join("down").exists());
+    assert_eq!(fs::read_to_string(&control)?, "d");
+
+    // --wait only makes sense with --now
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["disable", "--wait", "foo"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn remove_stop_wait_test() -> Result<()> {
+    let cfg = setup_tmp_config("remove-wait")?;
+
+    // services are symlinks to a directory outside of the service dir
+    let avail = cfg.service_path.with_file_name("sv");
+    fs::create_dir(&avail)?;
+    for (name, pid) in [("foo", "123"), ("bar", "223")] {
+        create_service(&cfg, name, "run", Some(pid), Some("-"))?;
+        let dir = cfg.service_path.join(name);
+        for control in
+            [dir.join("supervise/control"), dir.join("log/supervise/control")]
+        {
+            write_file(&control, "")?;
+        }
+        fs::rename(&dir, avail.join(name))?;
+        symlink(avail.join(name), &dir)?;
+    }
+
+    // a runsv process for foo, running from inside the service directory
+    // (runsv chdirs into it), and one for a foo in another svdir
+    let runsv = cfg.proc_path.join("100");
+    fs::create_dir(&runsv)?;
+    write_file(&runsv.join("cmdline"), "runsv\0foo\0")?;
+    symlink(avail.join("foo"), runsv.join("cwd"))?;
+    let other = cfg.proc_path.join("101");
+    fs::create_dir(&other)?;
+    write_file(&other.join("cmdline"), "runsv\0foo\0")?;
+    symlink(&cfg.service_path, other.join("cwd"))?;
+
+    // "runsv exits" a little while after the symlink is removed
+    let thread_runsv = runsv.clone();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        fs::remove_dir_all(thread_runsv)
+    });
+
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["remove", "--stop", "--wait", "foo"]).assert().success();
+    handle.join().unwrap()?;
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert_eq!(
+        stdout,
+        "removing service foo... stopped, done, waiting for runsv... exited\n\
+         warning: process 123 (foo-cmd) is still running\n"
+    );
+    assert!(!cfg.service_path.join("foo").exists());
+    for control in ["foo/supervise/control", "foo/log/supervise/control"] {
+        assert_eq!(fs::read_to_string(avail.join(control))?, "d");
+    }
+
+    // runsv never exits for bar
+    let runsv = cfg.proc_path.join("200");
+    fs::create_dir(&runsv)?;
+    write_file(&runsv.join("cmdline"), "runsv\0bar\0")?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVWAIT", "0");
+    let assert = cmd.args(["remove", "-w", "bar"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.ends_with("failed! runsv still running after 0s\n"));
+    assert_eq!(fs::read_to_string(avail.join("bar/supervise/control"))?, "");
+
+    Ok(())
+}
+
+#[test]
+fn avail_dirs_test() -> Result<()> {
+    let cfg = setup_tmp_config("avail-dirs")?;
+    let tmp = cfg.service_path.parent().unwrap().to_path_buf();
+
+    for dir in ["a/foo", "b/foo", "b/bar", "c/baz", "home/runit/sv/qux"] {
+        fs::create_dir_all(tmp.join(dir))?;
+    }
+    let search_path =
+        format!("{}:{}", tmp.join("a").display(), tmp.join("b").display());
+
+    // the first dir in the search path wins
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &search_path);
+    let assert = cmd.arg("avail").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(2)
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    let a = tmp.join("a").display().to_string();
+    let b = tmp.join("b").display().to_string();
+    assert_eq!(
+        lines,
+        [["bar", "avail", "---", &b], ["foo", "avail", "---", &a]]
+    );
+
+    // add and wait for "runsv" to create supervise/
+    let foo = cfg.service_path.join("foo");
+    let supervise = tmp.join("a/foo/supervise");
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        fs::create_dir(&supervise)?;
+        fs::write(supervise.join("control"), "")
+    });
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &search_path);
+    let assert =
+        cmd.args(["add", "--wait", "--start", "foo"]).assert().success();
+    handle.join().unwrap()?;
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert_eq!(
+        stdout,
+        "adding service foo... done, waiting for runsv... supervised, started\n"
+    );
+    assert_eq!(fs::read_link(&foo)?, tmp.join("a/foo"));
+    assert_eq!(fs::read_to_string(foo.join("supervise/control"))?, "u");
+
+    // --down creates the down file before linking
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &search_path);
+    cmd.env("SVWAIT", "0");
+    let assert = cmd.args(["add", "-w", "--down", "bar"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("failed! timed out after 0s"));
+    assert!(tmp.join("b/bar/down").exists());
+
+    // and both are undone together
+    let mut cmd = vsv(&cfg)?;
+    cmd.arg("undo").assert().success();
+    assert!(!tmp.join("b/bar/down").exists());
+    assert!(fs::symlink_metadata(cfg.service_path.join("bar")).is_err());
+
+    // the config file is used when SVAVAIL isn't set
+    let config_dir = cfg.service_path.with_file_name("config");
+    fs::create_dir(&config_dir)?;
+    write_file(
+        &config_dir.join("config"),
+        &format!("# avail dirs\navail_dir = {}\n", tmp.join("c").display()),
+    )?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["add", "baz"]).assert().success();
+    assert_eq!(fs::read_link(cfg.service_path.join("baz"))?, tmp.join("c/baz"));
+
+    // --avail-dir overrides everything else
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &search_path);
+    let assert = cmd.args(["--avail-dir", &b, "avail"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.starts_with(&format!("Available services in {}:", b)));
+
+    // user mode defaults to ~/runit/sv
+    let mut cmd = vsv(&cfg)?;
+    fs::remove_file(config_dir.join("config"))?;
+    cmd.env("HOME", tmp.join("home"));
+    let assert = cmd.args(["-u", "avail"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("qux"));
+
+    Ok(())
+}
+
+#[test]
+fn broken_services_test() -> Result<()> {
+    let cfg = setup_tmp_config("broken")?;
+
+    create_service(&cfg, "foo", "run", Some("123"), None)?;
+    for name in ["gone", "gone2", "gone3"] {
+        symlink(format!("/nonexistent/{}", name), cfg.service_path.join(name))?;
+    }
+
+    // broken symlinks show in status
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.arg("status").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let status = parse_status_output(stdout)?;
+    let states: Vec<(&str, &str)> = status
+        .iter()
+        .map(|line| (line[1].trim_end(), line[2].trim_end()))
+        .collect();
+    assert_eq!(
+        states,
+        [
+            ("foo", "run"),
+            ("gone", "broken"),
+            ("gone2", "broken"),
+            ("gone3", "broken")
+        ]
+    );
+
+    // and can be removed
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["remove", "gone"]).assert().success();
+    assert!(fs::symlink_metadata(cfg.service_path.join("gone")).is_err());
+
+    // prune lists the rest, and only removes them after confirmation
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["prune", "-n"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("gone2 -> /nonexistent/gone2\n"));
+    assert!(stdout.contains("gone3 -> /nonexistent/gone3\n"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.arg("prune").write_stdin("n\n").assert().success();
+    assert!(fs::symlink_metadata(cfg.service_path.join("gone2")).is_ok());
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.arg("prune").write_stdin("y\n").assert().success();
+    for name in ["gone2", "gone3"] {
+        assert!(fs::symlink_metadata(cfg.service_path.join(name)).is_err());
+    }
+    assert!(cfg.service_path.join("foo").exists());
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["prune", "-y"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.starts_with("no broken services"));
+
+    Ok(())
+}
+
+#[test]
+fn new_service_test() -> Result<()> {
+    let cfg = setup_tmp_config("new")?;
+    let avail = cfg.service_path.with_file_name("sv");
+    let avail_s = avail.display().to_string();
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["new", "web", "--user", "www", "--check", "curl -sf localhost"]);
+    cmd.args(["--finish", "rm -f /run/web.pid", "--log", "vlogger", "--"]);
+    cmd.args(["httpd", "-f", "-p", "8080", "it's"]);
+    let assert = cmd.write_stdin("n\n").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.starts_with("creating service web... done\n"));
+
+    let web = avail.join("web");
+    assert_eq!(
+        fs::read_to_string(web.join("run"))?,
+        "#!/bin/sh\nexec 2>&1\nexec chpst -u www httpd -f -p 8080 'it'\\''s'\n"
+    );
+    assert_eq!(
+        fs::read_to_string(web.join("finish"))?,
+        "#!/bin/sh\nrm -f /run/web.pid\n"
+    );
+    assert_eq!(
+        fs::read_to_string(web.join("check"))?,
+        "#!/bin/sh\nexec curl -sf localhost\n"
+    );
+    assert_eq!(
+        fs::read_to_string(web.join("log/run"))?,
+        "#!/bin/sh\nexec vlogger -t web -p daemon\n"
+    );
+    for file in ["run", "finish", "check", "log/run"] {
+        let mode = fs::metadata(web.join(file))?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o755, "{} mode", file);
+    }
+
+    // declined to add it
+    assert!(fs::symlink_metadata(cfg.service_path.join("web")).is_err());
+
+    // it already exists
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["new", "web", "--", "true"]).assert().failure();
+
+    // templates can be overridden, and --add adds it right away
+    let templates = cfg.service_path.with_file_name("config").join("templates");
+    fs::create_dir_all(&templates)?;
+    write_file(
+        &templates.join("run"),
+        "#!/bin/sh\n# {{name}}\nexec {{command}}\n",
+    )?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["new", "--add", "sleeper", "--", "sleep", "inf"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(avail.join("sleeper/run"))?,
+        "#!/bin/sh\n# sleeper\nexec sleep inf\n"
+    );
+    assert_eq!(
+        fs::read_link(cfg.service_path.join("sleeper"))?,
+        avail.join("sleeper")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn template_instances_test() -> Result<()> {
+    let cfg = setup_tmp_config("templates")?;
+    let avail = cfg.service_path.with_file_name("sv");
+    let avail_s = avail.display().to_string();
+
+    // a template with a log service, env dir, and (package) supervise link
+    let template = avail.join("openvpn@");
+    fs::create_dir_all(template.join("log"))?;
+    fs::create_dir_all(template.join("env"))?;
+    write_file(&template.join("run"), "#!/bin/sh\nexec chpst -e ./env true\n")?;
+    write_file(&template.join("log/run"), "#!/bin/sh\n")?;
+    write_file(&template.join("env/VERBOSE"), "1\n")?;
+    write_file(&template.join("env/INSTANCE"), "template\n")?;
+    symlink("/run/runit/supervise.openvpn", template.join("supervise"))?;
+
+    for instance in ["openvpn@site-a", "openvpn@site-b"] {
+        let mut cmd = vsv(&cfg)?;
+        cmd.env("SVAVAIL", &avail_s);
+        let assert = cmd.args(["add", instance]).assert().success();
+        let stdout = str::from_utf8(&assert.get_output().stdout)?;
+        assert_eq!(
+            stdout,
+            format!("adding service {}... instantiated, done\n", instance)
+        );
+    }
+
+    // the instance is a symlink farm of the template
+    let site_a = avail.join("openvpn@site-a");
+    assert_eq!(fs::read_link(site_a.join("run"))?, template.join("run"));
+    assert_eq!(
+        fs::read_link(site_a.join("log/run"))?,
+        template.join("log/run")
+    );
+    assert!(fs::symlink_metadata(site_a.join("supervise")).is_err());
+    assert_eq!(fs::read_to_string(site_a.join("env/INSTANCE"))?, "site-a\n");
+    assert_eq!(
+        fs::read_to_string(template.join("env/INSTANCE"))?,
+        "template\n"
+    );
+    assert_eq!(
+        fs::read_link(site_a.join("env/VERBOSE"))?,
+        template.join("env/VERBOSE")
+    );
+    assert_eq!(fs::read_link(cfg.service_path.join("openvpn@site-a"))?, site_a);
+
+    // templates themselves can't be added
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["add", "openvpn@"]).assert().failure();
+
+    // the instance is removed again if linking it fails
+    let site_c = cfg.service_path.join("openvpn@site-c");
+    symlink("/nonexistent", &site_c)?;
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["add", "openvpn@site-c"]).assert().failure();
+    assert!(!avail.join("openvpn@site-c").exists());
+    fs::remove_file(&site_c)?;
+
+    // avail shows the template and its instances
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    let assert = cmd.arg("avail").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(2)
+        .map(|l| l.split_whitespace().take(2).collect())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["openvpn@", "template"],
+            ["openvpn@site-a", "added"],
+            ["openvpn@site-b", "added"]
+        ]
+    );
+
+    // status groups the instances under the template
+    create_service(&cfg, "other", "run", Some("123"), None)?;
+    for instance in ["openvpn@site-a", "openvpn@site-b"] {
+        let supervise = avail.join(instance).join("supervise");
+        fs::create_dir(&supervise)?;
+        fs::write(supervise.join("status"), make_status("down", None))?;
+    }
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.arg("status").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let status = parse_status_output(stdout)?;
+    let names: Vec<&str> =
+        status.iter().map(|line| line[1].trim_end()).collect();
+    assert_eq!(names, ["openvpn@", "@site-a", "@site-b", "other"]);
+
+    Ok(())
+}
+
+#[test]
+fn clone_test() -> Result<()> {
+    let cfg = setup_tmp_config("clone")?;
+    let avail = cfg.service_path.with_file_name("sv");
+    let avail_s = avail.display().to_string();
+
+    let dhcpcd = avail.join("dhcpcd");
+    fs::create_dir_all(dhcpcd.join("log/supervise"))?;
+    write_file(&dhcpcd.join("run"), "#!/bin/sh\nexec dhcpcd -B eth0\n")?;
+    fs::set_permissions(dhcpcd.join("run"), fs::Permissions::from_mode(0o755))?;
+    write_file(
+        &dhcpcd.join("log/run"),
+        "#!/bin/sh\nmkdir -p /var/log/dhcpcd\nexec vlogger -t dhcpcd-x -t dhcpcd\n",
+    )?;
+    symlink("/run/runit/supervise.dhcpcd", dhcpcd.join("supervise"))?;
+    symlink("/usr/bin/true", dhcpcd.join("finish"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    let assert =
+        cmd.args(["clone", "dhcpcd", "dhcpcd-eth1"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("rewrote the logger tag"));
+
+    let clone = avail.join("dhcpcd-eth1");
+    assert_eq!(
+        fs::read_to_string(clone.join("run"))?,
+        "#!/bin/sh\nexec dhcpcd -B eth0\n"
+    );
+    let mode = fs::metadata(clone.join("run"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+    assert_eq!(
+        fs::read_to_string(clone.join("log/run"))?,
+        "#!/bin/sh\nmkdir -p /var/log/dhcpcd-eth1\nexec vlogger -t dhcpcd-x -t dhcpcd-eth1\n"
+    );
+    assert_eq!(
+        fs::read_link(clone.join("finish"))?,
+        PathBuf::from("/usr/bin/true")
+    );
+    assert!(fs::symlink_metadata(clone.join("supervise")).is_err());
+    assert!(fs::symlink_metadata(clone.join("log/supervise")).is_err());
+    assert!(fs::symlink_metadata(cfg.service_path.join("dhcpcd-eth1")).is_err());
+
+    // the destination already exists
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["clone", "dhcpcd", "dhcpcd-eth1"]).assert().failure();
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    cmd.args(["clone", "--add", "dhcpcd", "dhcpcd-eth2"]).assert().success();
+    assert_eq!(
+        fs::read_link(cfg.service_path.join("dhcpcd-eth2"))?,
+        avail.join("dhcpcd-eth2")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn conf_test() -> Result<()> {
+    let cfg = setup_tmp_config("conf")?;
+    let avail = cfg.service_path.with_file_name("sv");
+    let avail_s = avail.display().to_string();
+
+    // an added service, edited through the svdir symlink
+    let sshd = avail.join("sshd");
+    fs::create_dir_all(sshd.join("supervise"))?;
+    fs::write(sshd.join("supervise/status"), make_status("run", Some("701")))?;
+    fs::write(sshd.join("supervise/control"), "")?;
+    write_file(
+        &sshd.join("conf"),
+        "# sshd options\nOPTS=-4\nexport PORT=22\nOPTS=-6\n",
+    )?;
+    fs::set_permissions(sshd.join("conf"), fs::Permissions::from_mode(0o600))?;
+    symlink(&sshd, cfg.service_path.join("sshd"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["conf", "sshd"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains(&sshd.join("conf").display().to_string()));
+    assert!(stdout.contains("OPTS=-6"));
+    assert!(stdout.contains("PORT=22"));
+    assert!(!stdout.contains("OPTS=-4"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "set", "OPTS=-D -e", "EXTRA=$HOME"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(sshd.join("conf"))?,
+        "# sshd options\nOPTS=\"-D -e\"\nexport PORT=22\nEXTRA=\"\\$HOME\"\n"
+    );
+    let mode = fs::metadata(sshd.join("conf"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(fs::symlink_metadata(cfg.service_path.join("sshd"))?
+        .file_type()
+        .is_symlink());
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "unset", "PORT", "--restart"]).assert().success();
+    assert_eq!(
+        fs::read_to_string(sshd.join("conf"))?,
+        "# sshd options\nOPTS=\"-D -e\"\nEXTRA=\"\\$HOME\"\n"
+    );
+    assert_eq!(fs::read_to_string(sshd.join("supervise/control"))?, "tcu");
+
+    // invalid input
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "set", "1BAD=x"]).assert().failure();
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "set", "NOVALUE"]).assert().failure();
+
+    // values aren't written to the audit log
+    let audit_log =
+        fs::read_to_string(cfg.service_path.with_file_name("audit.log"))?;
+    assert!(audit_log.contains("\tconf set\tsshd\tOPTS set\tok"));
+    assert!(audit_log.contains("\tconf set\tsshd\tEXTRA unset\tok"));
+    assert!(!audit_log.contains("-4") && !audit_log.contains("-6"));
+
+    // multi-line values are left to `conf edit`
+    let conf = "OPTS=\"-D\n  -e\" # it's long\nPORT='22' # it's short\n";
+    write_file(&sshd.join("conf"), conf)?;
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["conf", "sshd", "set", "OPTS=-4"]).assert().failure();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stderr.contains("OPTS spans multiple lines"));
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "unset", "OPTS"]).assert().failure();
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "set", "OPTS=a\nb"]).assert().failure();
+    assert_eq!(fs::read_to_string(sshd.join("conf"))?, conf);
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "sshd", "set", "PORT=2222"]).assert().success();
+    assert_eq!(
+        fs::read_to_string(sshd.join("conf"))?,
+        "OPTS=\"-D\n  -e\" # it's long\nPORT=2222\n"
+    );
+
+    // a service that is only in the avail dir, without a conf file yet
+    fs::create_dir_all(avail.join("cronie"))?;
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s);
+    let assert = cmd.args(["conf", "cronie"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("no conf file"));
+
+    // edit with $EDITOR
+    let editor = cfg.service_path.with_file_name("editor.sh");
+    write_file(&editor, "#!/bin/sh\necho 'OPTS=-s' >> \"$1\"\n")?;
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail_s).env_remove("VISUAL").env("EDITOR", &editor);
+    cmd.args(["conf", "cronie", "edit"]).assert().success();
+    assert_eq!(fs::read_to_string(avail.join("cronie/conf"))?, "OPTS=-s\n");
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["conf", "nope"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn env_test() -> Result<()> {
+    let cfg = setup_tmp_config("env")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    let app = avail.join("app");
+    fs::create_dir_all(app.join("config"))?;
+    write_file(
+        &app.join("run"),
+        "#!/bin/sh\n# uses the env\nexec chpst -u app -e ./config app --serve\n",
+    )?;
+    write_file(&app.join("config/DB_PASSWORD"), "hunter2\n")?;
+    write_file(&app.join("config/PORT"), "8080\n")?;
+    write_file(&app.join("config/LANG"), "")?;
+    symlink(&app, cfg.service_path.join("app"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["env", "app"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains(&app.join("./config").display().to_string()));
+    assert!(stdout.contains("PORT=8080"));
+    assert!(stdout.contains("DB_PASSWORD=********"));
+    assert!(stdout.contains("LANG=(unset)"));
+    assert!(!stdout.contains("hunter2"));
+    assert!(!stdout.contains("warning"));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["env", "--reveal", "app"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("DB_PASSWORD=hunter2"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["env", "app", "set", "PORT=9090", "API_TOKEN=abc"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(app.join("config/PORT"))?, "9090\n");
+    assert_eq!(fs::read_to_string(app.join("config/API_TOKEN"))?, "abc\n");
+    let mode = fs::metadata(app.join("config/API_TOKEN"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["env", "app", "unset", "PORT", "NOT_THERE"]).assert().success();
+    assert!(!app.join("config/PORT").exists());
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["env", "app", "set", "BAD/NAME=x"]).assert().failure();
+
+    // the audit log doesn't record values
+    let audit_log =
+        fs::read_to_string(cfg.service_path.with_file_name("audit.log"))?;
+    assert!(audit_log.contains("env set"));
+    assert!(!audit_log.contains("abc"));
+
+    // no `-e` in the run script: `env/` is used, with a warning
+    let plain = avail.join("plain");
+    fs::create_dir_all(&plain)?;
+    write_file(&plain.join("run"), "#!/bin/sh\nexec chpst -u nobody plain\n")?;
+    symlink(&plain, cfg.service_path.join("plain"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["env", "plain", "set", "FOO=bar"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("doesn't use chpst -e"));
+    assert_eq!(fs::read_to_string(plain.join("env/FOO"))?, "bar\n");
+
+    // grouped options and envdir
+    for (script, dir) in [
+        ("exec chpst -ve/etc/app/env app", "/etc/app/env"),
+        ("exec chpst -u nobody:nobody -/ /srv -e env app", "env"),
+        ("exec chpst -p 64 -o 1024 -e ./env app", "./env"),
+        ("exec chpst -C /srv -e env app", "env"),
+        ("exec envdir ./vars app", "./vars"),
+    ] {
+        write_file(&plain.join("run"), &format!("#!/bin/sh\n{}\n", script))?;
+        let mut cmd = vsv(&cfg)?;
+        let assert = cmd.args(["env", "plain"]).assert().success();
+        let stdout = str::from_utf8(&assert.get_output().stdout)?;
+        assert!(
+            stdout.contains(&plain.join(dir).display().to_string()),
+            "{}: {}",
+            script,
+            stdout
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn cat_test() -> Result<()> {
+    let cfg = setup_tmp_config("cat")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    let app = avail.join("app");
+    fs::create_dir_all(app.join("log"))?;
+    fs::create_dir_all(app.join("env"))?;
+    write_file(
+        &app.join("run"),
+        "#!/bin/sh\n[ -r conf ] && . ./conf\nexec app $OPTS\n",
+    )?;
+    fs::set_permissions(app.join("run"), fs::Permissions::from_mode(0o755))?;
+    write_file(&app.join("finish"), "#!/bin/sh\necho finished\n")?;
+    fs::set_permissions(app.join("finish"), fs::Permissions::from_mode(0o644))?;
+    write_file(&app.join("conf"), "OPTS='-v'\n")?;
+    fs::write(app.join("env/MOTD"), "hello\0world\n")?;
+    fs::write(app.join("env/DB_PASSWORD"), "hunter2")?;
+
+    // log/run is a symlink to a binary
+    let logger = cfg.service_path.with_file_name("vlogger");
+    fs::write(&logger, b"\x7fELF\0\0\x01")?;
+    fs::set_permissions(&logger, fs::Permissions::from_mode(0o755))?;
+    symlink(&logger, app.join("log/run"))?;
+
+    symlink(&app, cfg.service_path.join("app"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["cat", "app"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+
+    let run_header = format!("==> {} (0755) <==\n", app.join("run").display());
+    assert!(stdout.contains(&format!("{}#!/bin/sh\n", run_header)));
+    assert!(stdout.contains("exec app $OPTS\n"));
+    assert!(stdout.contains(&format!(
+        "==> {} (0644) <== not executable!\n",
+        app.join("finish").display()
+    )));
+    assert!(stdout.contains(&format!(
+        "==> {} (0755) <==\n(binary file, skipped)\n",
+        logger.display()
+    )));
+    assert!(stdout.contains("OPTS='-v'\n"));
+    assert!(stdout.contains("hello\nworld\n"));
+    assert!(stdout.contains(&format!(
+        "==> {} (0644) <==\n********\n",
+        app.join("env/DB_PASSWORD").display()
+    )));
+    assert!(!stdout.contains("hunter2"));
+    assert!(!stdout.contains("\x1b["));
+
+    // the order is run, finish, conf, log/run, env
+    let pos = |s: &str| stdout.find(s).unwrap_or(usize::MAX);
+    assert!(pos("#!/bin/sh") < pos("finished"));
+    assert!(pos("finished") < pos("OPTS='-v'"));
+    assert!(pos("OPTS='-v'") < pos("binary file"));
+    assert!(pos("binary file") < pos("hello"));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["--color", "yes", "cat", "app"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("\x1b["));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["cat", "--reveal", "app"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("hunter2\n"));
+    assert!(!stdout.contains("********"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["cat", "nope"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn edit_test() -> Result<()> {
+    let cfg = setup_tmp_config("edit")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    let app = avail.join("app");
+    fs::create_dir_all(app.join("supervise"))?;
+    fs::write(app.join("supervise/status"), make_status("run", Some("801")))?;
+    fs::write(app.join("supervise/control"), "")?;
+    write_file(&app.join("run"), "#!/bin/sh\nexec app\n")?;
+    fs::set_permissions(app.join("run"), fs::Permissions::from_mode(0o750))?;
+    symlink(&app, cfg.service_path.join("app"))?;
+
+    // the "editor" replaces the file with $NEW_CONTENT (and notes the modes
+    // of the scratch file and its directory)
+    let editor = cfg.service_path.with_file_name("editor.sh");
+    let modes = cfg.service_path.with_file_name("modes");
+    write_file(
+        &editor,
+        &format!(
+            "#!/bin/sh\nstat -c %a \"${{1%/*}}\" \"$1\" > {}\n\
+             printf '%b' \"$NEW_CONTENT\" > \"$1\"\n",
+            modes.display()
+        ),
+    )?;
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755))?;
+    let tmp_dir = cfg.service_path.with_file_name("tmp");
+    fs::create_dir(&tmp_dir)?;
+
+    let edit = |content: &str| -> Result<Command> {
+        let mut cmd = vsv(&cfg)?;
+        cmd.env_remove("VISUAL")
+            .env("EDITOR", &editor)
+            .env("TMPDIR", &tmp_dir)
+            .env("NEW_CONTENT", content);
+        Ok(cmd)
+    };
+
+    // syntax errors are rejected (and not editing again keeps the original)
+    edit("#!/bin/sh\nif then\n")?
+        .args(["edit", "app"])
+        .write_stdin("n\n")
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(app.join("run"))?, "#!/bin/sh\nexec app\n");
+
+    // the scratch copy is private and cleaned up
+    assert_eq!(fs::read_to_string(&modes)?, "700\n600\n");
+    assert_eq!(fs::read_dir(&tmp_dir)?.count(), 0);
+
+    // valid changes are written, keeping the mode, and restart is offered
+    let assert = edit("#!/bin/sh\nexec app -v\n")?
+        .args(["edit", "app"])
+        .write_stdin("y\n")
+        .assert()
+        .success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("restart service app now?"));
+    assert_eq!(
+        fs::read_to_string(app.join("run"))?,
+        "#!/bin/sh\nexec app -v\n"
+    );
+    let mode = fs::metadata(app.join("run"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o750);
+    assert_eq!(fs::read_to_string(app.join("supervise/control"))?, "tcu");
+
+    // no changes
+    let assert = edit("#!/bin/sh\nexec app -v\n")?
+        .args(["edit", "app"])
+        .assert()
+        .success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("no changes"));
+
+    // a new file (scripts are created executable)
+    edit("#!/bin/sh\nexec vlogger -t app\n")?
+        .args(["edit", "app", "log/run"])
+        .write_stdin("n\n")
+        .assert()
+        .success();
+    let mode = fs::metadata(app.join("log/run"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+
+    edit("x")?.args(["edit", "app", "../other"]).assert().failure();
+
+    // files owned by a package need --force
+    write_xbps_db(
+        &cfg,
+        &[("app", "app-1.0_1", "An app", &[(&app.join("run"), "0000")])],
+    )?;
+
+    let assert = edit("#!/bin/sh\nexec app -q\n")?
+        .args(["edit", "app"])
+        .assert()
+        .failure();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stderr.contains("owned by package app"));
+    assert_eq!(
+        fs::read_to_string(app.join("run"))?,
+        "#!/bin/sh\nexec app -v\n"
+    );
+
+    edit("#!/bin/sh\nexec app -q\n")?
+        .args(["edit", "--force", "app"])
+        .write_stdin("n\n")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(app.join("run"))?,
+        "#!/bin/sh\nexec app -q\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lint_test() -> Result<()> {
+    let cfg = setup_tmp_config("lint")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    let script = |dir: &Path, file: &str, content: &str| -> Result<()> {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap())?;
+        write_file(&path, content)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    };
+
+    // a clean service
+    let good = avail.join("good");
+    script(&good, "run", "#!/bin/sh\n[ -r conf ] && . ./conf\nexec good\n")?;
+    script(&good, "log/run", "#!/bin/sh\nexec vlogger -t good\n")?;
+    symlink("/run/runit/supervise.good", good.join("supervise"))?;
+    symlink(&good, cfg.service_path.join("good"))?;
+
+    // a service with every mistake
+    let bad = avail.join("bad");
+    script(
+        &bad,
+        "run",
+        "#!/bin/sh\nsv check good nope idle >/dev/null || exit 1\nbad --foreground\n",
+    )?;
+    script(&bad, "finish", "echo no shebang\n")?;
+    script(&bad, "log/run", "#!/bin/sh\nexec cat > /tmp/log\n")?;
+    write_file(&bad.join("conf"), "OPTS=-v\r\n")?;
+    write_file(&bad.join("down"), "")?;
+    fs::create_dir(bad.join("supervise"))?;
+    symlink(&bad, cfg.service_path.join("bad"))?;
+
+    // not executable, and in the avail dir but not added
+    let idle = avail.join("idle");
+    script(&idle, "run", "#!/bin/sh\r\nexec idle\r\n")?;
+    fs::set_permissions(idle.join("run"), fs::Permissions::from_mode(0o644))?;
+
+    // a service dir without a run script
+    fs::create_dir(cfg.service_path.join("empty"))?;
+
+    let lint = || -> Result<Command> {
+        let mut cmd = vsv(&cfg)?;
+        cmd.env("SVAVAIL", &avail);
+        Ok(cmd)
+    };
+
+    lint()?.args(["lint", "good"]).assert().success();
+
+    let assert = lint()?.args(["lint"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let expected = [
+        "bad/run: warning: last command doesn't use exec",
+        "bad/run: warning: sv check on idle which isn't added",
+        "bad/run: error: sv check on nope which doesn't exist",
+        "bad/finish: error: missing shebang",
+        "bad/log/run: warning: doesn't run a logger",
+        "bad/conf: error: CRLF line endings",
+        "bad/down: warning: down file in the avail dir",
+        "bad/supervise: warning: runtime directory in the avail dir",
+        "empty/run: error: missing",
+        "good: ok",
+        "3 service(s) checked",
+    ];
+    for line in expected {
+        assert!(stdout.contains(line), "{:?} not in:\n{}", line, stdout);
+    }
+    assert!(!stdout.contains("sv check on good"));
+
+    let assert = lint()?.args(["lint", "--json", "idle"]).assert().failure();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.starts_with("[{\"service\":\"idle\","));
+    assert!(stdout.contains("\"errors\":2,\"warnings\":0"));
+    assert!(stdout.contains(
+        "{\"level\":\"error\",\"file\":\"run\",\"message\":\"not executable\"}"
+    ));
+    assert!(stdout.contains("\"message\":\"CRLF line endings\""));
+
+    Ok(())
+}
+
+#[test]
+fn avail_search_test() -> Result<()> {
+    let cfg = setup_tmp_config("avail-search")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    // description file
+    let nm = avail.join("NetworkManager");
+    fs::create_dir_all(&nm)?;
+    write_file(&nm.join("description"), "\nNetwork connection manager\n")?;
+    write_file(&nm.join("run"), "#!/bin/sh\n# ignored\nexec NetworkManager\n")?;
+
+    // first comment in run
+    let ntpd = avail.join("openntpd");
+    fs::create_dir_all(&ntpd)?;
+    write_file(
+        &ntpd.join("run"),
+        "#!/bin/sh\n\n# Network time daemon\nexec ntpd -d\n",
+    )?;
+
+    // description of the owning package
+    let sshd = avail.join("sshd");
+    fs::create_dir_all(&sshd)?;
+    write_file(&sshd.join("run"), "#!/bin/sh\nexec /usr/bin/sshd -D\n")?;
+    symlink(&sshd, cfg.service_path.join("sshd"))?;
+
+    let xbps = cfg.service_path.with_file_name("xbps");
+    fs::create_dir_all(&xbps)?;
+    write_file(
+        &xbps.join("pkgdb-0.38.plist"),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>openssh</key>
+	<dict>
+		<key>automatic-install</key>
+		<false/>
+		<key>installed_size</key>
+		<integer>4523007</integer>
+		<key>pkgver</key>
+		<string>openssh-9.8p1_1</string>
+		<key>short_desc</key>
+		<string>OpenSSH free Secure Shell (SSH) client &amp; server</string>
+		<key>shlib-requires</key>
+		<array>
+			<string>libc.so.6</string>
+		</array>
+	</dict>
+</dict>
+</plist>
+"#,
+    )?;
+    write_file(
+        &xbps.join(".openssh-files.plist"),
+        &format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>files</key>
+	<array>
+		<dict>
+			<key>file</key>
+			<string>{}</string>
+			<key>sha256</key>
+			<string>0000</string>
+		</dict>
+	</array>
+</dict>
+</plist>
+"#,
+            fs::canonicalize(&sshd)?.join("run").display()
+        ),
+    )?;
+
+    let avail_cmd = |args: &[&str]| -> Result<String> {
+        let mut cmd = vsv(&cfg)?;
+        cmd.env("SVAVAIL", &avail).arg("avail").args(args);
+        let assert = cmd.assert().success();
+        Ok(str::from_utf8(&assert.get_output().stdout)?.to_string())
+    };
+
+    let stdout = avail_cmd(&[])?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].ends_with("DESCRIPTION"));
+    assert!(lines[2].starts_with("NetworkManager"));
+    assert!(lines[2].ends_with(" Network connection manager"));
+    assert!(lines[3].ends_with(" Network time daemon"));
+    assert!(
+        lines[4].ends_with(" OpenSSH free Secure Shell (SSH) client & server")
+    );
+
+    let names = |stdout: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .skip(2)
+            .map(|l| l.split_whitespace().next().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        names(&avail_cmd(&["--search", "network"])?),
+        ["NetworkManager", "openntpd"]
+    );
+    assert_eq!(names(&avail_cmd(&["-s", "nmgr"])?), ["NetworkManager"]);
+    assert_eq!(names(&avail_cmd(&["-s", "secure shell"])?), ["sshd"]);
+    assert!(names(&avail_cmd(&["-s", "nothing matches"])?).is_empty());
+    assert_eq!(names(&avail_cmd(&["--added"])?), ["sshd"]);
+    assert_eq!(
+        names(&avail_cmd(&["--not-added", "-s", "time"])?),
+        ["openntpd"]
+    );
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["avail", "--added", "--not-added"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn package_owner_test() -> Result<()> {
+    let cfg = setup_tmp_config("package-owner")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    create_service(&cfg, "sshd", "run", Some("901"), None)?;
+    create_service(&cfg, "local", "run", Some("902"), None)?;
+    let sshd_run = fs::canonicalize(cfg.service_path.join("sshd"))?.join("run");
+
+    let dhcpcd = avail.join("dhcpcd");
+    fs::create_dir_all(&dhcpcd)?;
+    let dhcpcd_run = fs::canonicalize(&dhcpcd)?.join("run");
+    write_file(&dhcpcd_run, "#!/bin/sh\nexec dhcpcd -B\n")?;
+
+    write_xbps_db(
+        &cfg,
+        &[
+            ("openssh", "openssh-9.8p1_1", "OpenSSH", &[(&sshd_run, "00")]),
+            (
+                "dhcpcd",
+                "dhcpcd-10.0.6_1",
+                "DHCP client",
+                &[(&dhcpcd_run, "00")],
+            ),
+            ("bash", "bash-5.2.32_1", "GNU shell", &[]),
+        ],
+    )?;
+
+    // status
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "-p"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[1].trim_end().ends_with("PACKAGE"));
+    assert!(lines[2].contains("local") && lines[2].trim_end().ends_with("---"));
+    assert!(lines[3].contains("sshd"));
+    assert!(lines[3].trim_end().ends_with("openssh-9.8p1_1"));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "--json", "sshd"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.starts_with("[{\"name\":\"sshd\",\"state\":\"run\","));
+    assert!(stdout.contains("\"pid\":901,"));
+    assert!(stdout.contains(
+        "\"package\":{\"name\":\"openssh\",\"version\":\"9.8p1_1\"}}]"
+    ));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "--json", "local"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("\"package\":null"));
+
+    // avail
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    let assert = cmd.arg("avail").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let line = stdout.lines().find(|l| l.starts_with("dhcpcd")).unwrap();
+    let columns: Vec<&str> = line.split_whitespace().collect();
+    assert_eq!(columns[..3], ["dhcpcd", "avail", "dhcpcd-10.0.6_1"]);
+    assert!(line.ends_with(" DHCP client"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    let assert = cmd.args(["avail", "--json"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert_eq!(
+        stdout.trim_end(),
+        format!(
+            "[{{\"name\":\"dhcpcd\",\"status\":\"avail\",\"source\":{:?},\"description\":\"DHCP client\",\"package\":{{\"name\":\"dhcpcd\",\"version\":\"10.0.6_1\"}}}}]",
+            avail.display().to_string()
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn diff_test() -> Result<()> {
+    let cfg = setup_tmp_config("diff")?;
+    let avail = cfg.service_path.with_file_name("sv");
+
+    let sshd = avail.join("sshd");
+    fs::create_dir_all(sshd.join("log"))?;
+    let sshd = fs::canonicalize(&sshd)?;
+    write_file(&sshd.join("run"), "#!/bin/sh\nexec sshd -D\n")?;
+    write_file(&sshd.join("log/run"), "#!/bin/sh\nexec vlogger -t ssh\n")?;
+    write_file(&sshd.join("conf"), &format!("# {}\n", "x".repeat(200)))?;
+    write_file(&sshd.join("local.conf"), "OPTS=-v\n")?;
+    write_file(&sshd.join("down"), "")?;
+    symlink("/run/runit/supervise.sshd", sshd.join("supervise"))?;
+    symlink(&sshd, cfg.service_path.join("sshd"))?;
+
+    let dhcpcd = avail.join("dhcpcd");
+    fs::create_dir_all(&dhcpcd)?;
+    let dhcpcd = fs::canonicalize(&dhcpcd)?;
+    write_file(&dhcpcd.join("run"), "#!/bin/sh\nexec dhcpcd -B\n")?;
+
+    write_xbps_db(
+        &cfg,
+        &[
+            (
+                "openssh",
+                "openssh-9.8p1_1",
+                "OpenSSH",
+                &[
+                    (
+                        &sshd.join("run"),
+                        "26ea6159a3af63f667def7ddf52933e078dfda8006506a372f4fb461448813ad",
+                    ),
+                    (
+                        &sshd.join("log/run"),
+                        "65339dce411ca5b4253a9bea0bdb908f8f187a6fe0c6cc429e12af132b62b3ca",
+                    ),
+                    (
+                        &sshd.join("conf"),
+                        "0BF187F07984A373CB74382549706EFF129F90FDB01DC2853E540D364364BAED",
+                    ),
+                    (&sshd.join("finish"), "00"),
+                ],
+            ),
+            (
+                "dhcpcd",
+                "dhcpcd-10.0.6_1",
+                "DHCP client",
+                &[(
+                    &dhcpcd.join("run"),
+                    "b18d71a7d80d1568da49638bb945f10160ee1aeb58b510df33780b4fc8413941",
+                )],
+            ),
+        ],
+    )?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["diff", "sshd"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(1)
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            ["deleted", "finish"],
+            ["added", "local.conf"],
+            ["modified", "log/run"]
+        ]
+    );
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    let assert = cmd.args(["diff", "dhcpcd"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("(dhcpcd-10.0.6_1)"));
+    assert!(stdout.contains("no local changes"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    let assert = cmd.args(["avail", "--modified"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let names: Vec<&str> = stdout
+        .lines()
+        .skip(2)
+        .filter_map(|l| l.split_whitespace().next())
+        .collect();
+    assert_eq!(names, ["sshd"]);
+
+    // services that can't be checked are shown as unknown
+    fs::remove_file(dhcpcd.join("run"))?;
+    fs::create_dir(dhcpcd.join("run"))?;
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    let assert = cmd.args(["avail", "--modified"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(2)
+        .map(|l| l.split_whitespace().take(2).collect())
+        .collect();
+    assert_eq!(rows, [["dhcpcd", "unknown"], ["sshd", "added"]]);
+    assert!(stderr.contains("failed to check dhcpcd for changes"));
+
+    // not from a package
+    fs::create_dir_all(avail.join("local"))?;
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    cmd.args(["diff", "local"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn runlevel_test() -> Result<()> {
+    let cfg = setup_tmp_config("runlevel")?;
+    let avail = cfg.service_path.with_file_name("sv");
+    let runsvdir = cfg.service_path.with_file_name("runsvdir");
+
+    for name in ["sshd", "agetty-tty1"] {
+        fs::create_dir_all(avail.join(name))?;
+        write_file(&avail.join(name).join("run"), "#!/bin/sh\nexec true\n")?;
+    }
+    fs::create_dir_all(runsvdir.join("default"))?;
+    fs::create_dir_all(runsvdir.join("single"))?;
+    symlink("default", runsvdir.join("current"))?;
+    symlink(avail.join("sshd"), runsvdir.join("default/sshd"))?;
+    symlink(avail.join("agetty-tty1"), runsvdir.join("default/agetty-tty1"))?;
+
+    // list
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.arg("runlevel").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(2)
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(lines, [vec!["default", "2", "(current)"], vec!["single", "0"]]);
+
+    // add to a non-current runlevel
+    let mut cmd = vsv(&cfg)?;
+    cmd.env("SVAVAIL", &avail);
+    cmd.args(["add", "--runlevel", "single", "agetty-tty1"]).assert().success();
+    assert!(runsvdir.join("single/agetty-tty1").exists());
+    assert!(!cfg.service_path.join("agetty-tty1").exists());
+
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["status", "--runlevel", "single"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("agetty-tty1"));
+    assert!(!stdout.contains("sshd"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["status", "--runlevel", "nope"]).assert().failure();
+
+    // matrix
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["runlevel", "matrix"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines: Vec<Vec<&str>> =
+        stdout.lines().map(|l| l.split_whitespace().collect()).collect();
+    assert_eq!(
+        lines,
+        [
+            ["SERVICE", "default", "single"],
+            ["agetty-tty1", "✔", "✔"],
+            ["sshd", "✔", "·"]
+        ]
+    );
+
+    // switch
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["runlevel", "switch", "single"]).assert().success();
+    assert_eq!(fs::read_link(runsvdir.join("current"))?, Path::new("single"));
+    assert_eq!(fs::read_link(runsvdir.join("previous"))?, Path::new("default"));
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["runlevel", "switch", "single"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("already in runlevel"));
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["runlevel", "switch", "previous"]).assert().failure();
+
+    let audit =
+        fs::read_to_string(cfg.service_path.with_file_name("audit.log"))?;
+    assert!(audit.contains("runlevel switch"));
+
+    // remove from a non-current runlevel
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["remove", "--runlevel", "default", "sshd"]).assert().success();
+    assert!(!runsvdir.join("default/sshd").exists());
+
+    Ok(())
+}
+
+#[test]
+fn root_test() -> Result<()> {
+    let cfg = setup_tmp_config("root")?;
+    let root = cfg.service_path.with_file_name("root");
+    let avail = root.join("etc/sv");
+    let runsvdir = root.join("etc/runit/runsvdir");
+
+    for name in ["sshd", "agetty-tty1", "getty@"] {
+        fs::create_dir_all(avail.join(name))?;
+        write_file(&avail.join(name).join("run"), "#!/bin/sh\nexec true\n")?;
+    }
+    write_file(&avail.join("agetty-tty1/down"), "")?;
+    fs::create_dir_all(avail.join("agetty-tty1/supervise"))?;
+    fs::create_dir_all(runsvdir.join("default"))?;
+    fs::create_dir_all(root.join("var"))?;
+    symlink("default", runsvdir.join("current"))?;
+    // an image that hasn't booted, /run is empty
+    symlink("/run/runit/runsvdir/current", root.join("var/service"))?;
+    // absolute links are resolved inside the root, not on the host
+    symlink("/etc/sv/agetty-tty1", runsvdir.join("default/agetty-tty1"))?;
+
+    let vsv_root = || -> Result<Command> {
+        let mut cmd = vsv(&cfg)?;
+        for var in ["SVDIR", "PROC_DIR", "VSV_RUNLEVEL_DIR", "VSV_XBPS_DBDIR"] {
+            cmd.env_remove(var);
+        }
+        cmd.arg("--root").arg(&root);
+        Ok(cmd)
+    };
+
+    vsv_root()?.args(["add", "sshd"]).assert().success();
+    let link = runsvdir.join("default/sshd");
+    assert_eq!(fs::read_link(&link)?, Path::new("../../../sv/sshd"));
+    assert!(link.join("run").exists());
+
+    vsv_root()?.args(["add", "getty@tty2"]).assert().success();
+    assert_eq!(
+        fs::read_link(avail.join("getty@tty2/run"))?,
+        Path::new("../getty@/run")
+    );
+
+    let assert = vsv_root()?.arg("status").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    for name in ["agetty-tty1", "sshd", "getty@", "@tty2"] {
+        assert!(stdout.contains(name), "{} missing: {}", name, stdout);
+    }
+
+    vsv_root()?.args(["enable", "agetty-tty1"]).assert().success();
+    assert!(!avail.join("agetty-tty1/down").exists());
+
+    vsv_root()?.args(["remove", "sshd"]).assert().success();
+    assert!(fs::symlink_metadata(&link).is_err());
+    assert!(avail.join("sshd/run").exists());
+
+    // broken links are found inside the root: /usr exists on the host only,
+    // /etc/sv/agetty-tty1 in the root only
+    let gone = runsvdir.join("default/gone");
+    let agetty = runsvdir.join("default/agetty-tty1");
+    symlink("/usr", &gone)?;
+
+    let assert = vsv_root()?.arg("status").assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let status = parse_status_output(stdout)?;
+    let states: Vec<(&str, &str)> = status
+        .iter()
+        .map(|line| (line[1].trim_end(), line[2].trim_end()))
+        .filter(|(name, _)| ["agetty-tty1", "gone"].contains(name))
+        .collect();
+    assert_eq!(states, [("agetty-tty1", "n/a"), ("gone", "broken")]);
+
+    vsv_root()?.args(["prune", "-y"]).assert().success();
+    assert!(fs::symlink_metadata(&gone).is_err());
+    assert_eq!(fs::read_link(&agetty)?, Path::new("/etc/sv/agetty-tty1"));
+
+    vsv_root()?.args(["add", "agetty-tty1"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn target_pid_test() -> Result<()> {
+    let cfg = setup_tmp_config("target-pid")?;
+
+    // the container's view of the filesystem through /proc/<pid>/root
+    let root = cfg.proc_path.join("4949/root");
+    let inner = Config {
+        proc_path: root.join("proc"),
+        service_path: root.join("var/service"),
+    };
+    fs::create_dir_all(&inner.proc_path)?;
+    fs::create_dir_all(root.join("etc/sv"))?;
+    fs::create_dir_all(&inner.service_path)?;
+
+    // an absolute link inside the container
+    let web = Config {
+        proc_path: inner.proc_path.clone(),
+        service_path: root.join("etc/sv"),
+    };
+    create_service(&web, "web", "run", Some("17"), None)?;
+    write_file(&root.join("etc/sv/web/supervise/control"), "")?;
+    symlink("/etc/sv/web", inner.service_path.join("web"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env_remove("SVDIR");
+    let assert = cmd.args(["--target-pid", "4949"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("web"), "web missing: {}", stdout);
+    assert!(stdout.contains("web-cmd"), "container /proc not used: {}", stdout);
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env_remove("SVDIR");
+    cmd.args(["--target-pid", "4949", "restart", "web"]).assert().success();
+    let control =
+        fs::read_to_string(root.join("etc/sv/web/supervise/control"))?;
+    assert_eq!(control, "tcu");
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["--target-pid", "4950", "status"]).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn all_scopes_test() -> Result<()> {
+    let cfg = setup_tmp_config("all-scopes")?;
+    let alice = Config {
+        proc_path: cfg.proc_path.clone(),
+        service_path: cfg.service_path.with_file_name("alice"),
+    };
+    fs::create_dir(&alice.service_path)?;
+
+    create_service(&cfg, "sshd", "run", Some("5151"), None)?;
+    create_service(&alice, "sshd", "run", Some("5152"), None)?;
+    create_service(&alice, "mpd", "down", None, None)?;
+    write_file(&alice.service_path.join("sshd/supervise/control"), "")?;
+
+    let config_dir = cfg.service_path.with_file_name("config");
+    fs::create_dir_all(&config_dir)?;
+    write_file(
+        &config_dir.join("config"),
+        &format!(
+            "scopes = system={}:alice={}\n",
+            cfg.service_path.display(),
+            alice.service_path.display()
+        ),
+    )?;
+
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["status", "--all-scopes"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.split_whitespace().take(3).collect())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["SCOPE", "SERVICE", "STATE"],
+            ["✔", "system", "sshd"],
+            ["X", "alice", "mpd"],
+            ["✔", "alice", "sshd"]
+        ]
+    );
+
+    let mut cmd = vsv(&cfg)?;
+    let assert =
+        cmd.args(["status", "--all-scopes", "--json"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("\"pid\":5152,"));
+    assert!(stdout.contains("\"scope\":\"alice\"}"));
+
+    // scope:name picks the service in that scope
+    let mut cmd = vsv(&cfg)?;
+    cmd.args(["restart", "alice:sshd"]).assert().success();
+    let control = alice.service_path.join("sshd/supervise/control");
+    assert_eq!(fs::read_to_string(control)?, "tcu");
+    assert!(!cfg.service_path.join("sshd/supervise/control").exists());
+
+    // an unknown scope is an error, not the default svdir
+    let mut cmd = vsv(&cfg)?;
+    let assert = cmd.args(["restart", "nobody:sshd"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("unknown scope \"nobody\""), "{}", stdout);
+    assert!(!cfg.service_path.join("sshd/supervise/control").exists());
+
+    // without a scopes config entry, users come from the password database
+    let root = cfg.service_path.with_file_name("root");
+    fs::create_dir_all(root.join("etc"))?;
+    fs::create_dir_all(root.join("var/service"))?;
+    fs::create_dir_all(root.join("home/bob/runit/service/mpd"))?;
+    write_file(
+        &root.join("etc/passwd"),
+        "root:x:0:0::/root:/bin/sh\nbob:x:1000:1000::/home/bob:/bin/sh\n",
+    )?;
+    fs::remove_file(config_dir.join("config"))?;
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env_remove("SVDIR").arg("--root").arg(&root);
+    let assert = cmd.args(["status", "--all-scopes"]).assert().success();
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert!(stdout.contains("bob"), "bob missing: {}", stdout);
+
+    // the password database is only read for names that look like a scope
+    fs::remove_file(root.join("etc/passwd"))?;
+    let mut cmd = vsv(&cfg)?;
+    cmd.env_remove("SVDIR").arg("--root").arg(&root);
+    let assert = cmd.args(["enable", "./mpd:1"]).assert().failure();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(!stderr.contains("passwd"), "{}", stderr);
+
+    let mut cmd = vsv(&cfg)?;
+    cmd.env_remove("SVDIR").arg("--root").arg(&root);
+    let assert = cmd.args(["enable", "bob:mpd"]).assert().failure();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stderr.contains("passwd"), "{}", stderr);
+
+    Ok(())
+}